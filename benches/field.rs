@@ -0,0 +1,107 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ethers::{types::U256, utils::keccak256};
+use num_bigint::{BigInt, BigUint, Sign};
+use stark_evm_adapter::{default_prime, field::Felt252};
+
+/// Number of public memory cells in each benchmarked page.
+const N_CELLS: usize = 10_000;
+
+fn values(n: usize) -> Vec<U256> {
+    (0..n as u64)
+        .map(|i| U256::from_big_endian(&keccak256(i.to_be_bytes())) % default_prime())
+        .collect()
+}
+
+/// The previous `montgomery_encode`, reducing through `BigUint` for every element.
+fn montgomery_encode_biguint(element: &U256) -> U256 {
+    let prime = BigUint::parse_bytes(
+        b"800000000000011000000000000000000000000000000000000000000000001",
+        16,
+    )
+    .unwrap();
+    let mut bytes = [0u8; 32];
+    element.to_big_endian(&mut bytes);
+    let encoded = (BigUint::from_bytes_be(&bytes) << 256u32) % prime;
+    U256::from_big_endian(&encoded.to_bytes_be())
+}
+
+/// The previous `calculate_product`, reducing through `BigInt` for every cell.
+fn calculate_product_bigint(prod: U256, z: U256, alpha: U256, address: U256, value: U256) -> U256 {
+    let to_bigint = |value: U256| {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        BigInt::from_bytes_be(Sign::Plus, &bytes)
+    };
+    let multiply = to_bigint(prod)
+        * (to_bigint(z) - (to_bigint(address) + to_bigint(alpha) * to_bigint(value)));
+    let (_, bytes) = multiply
+        .modpow(&BigInt::from(1), &to_bigint(default_prime()))
+        .to_bytes_be();
+    U256::from_big_endian(&bytes)
+}
+
+fn bench_montgomery_encode(c: &mut Criterion) {
+    let values = values(N_CELLS);
+    let mut group = c.benchmark_group("montgomery_encode");
+    group.bench_with_input(
+        BenchmarkId::new("biguint", N_CELLS),
+        &values,
+        |b, values| {
+            b.iter(|| {
+                values
+                    .iter()
+                    .map(montgomery_encode_biguint)
+                    .collect::<Vec<U256>>()
+            })
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("felt252", N_CELLS),
+        &values,
+        |b, values| {
+            b.iter(|| {
+                values
+                    .iter()
+                    .map(|value| Felt252::from(*value).to_montgomery())
+                    .collect::<Vec<U256>>()
+            })
+        },
+    );
+    group.finish();
+}
+
+fn bench_page_product(c: &mut Criterion) {
+    let values = values(N_CELLS);
+    let (z, alpha) = (U256::from(0x1234567u64), U256::from(0x89abcdefu64));
+    let mut group = c.benchmark_group("page_product");
+    group.bench_with_input(BenchmarkId::new("bigint", N_CELLS), &values, |b, values| {
+        b.iter(|| {
+            values
+                .iter()
+                .enumerate()
+                .fold(U256::one(), |prod, (i, value)| {
+                    calculate_product_bigint(prod, z, alpha, U256::from(i), *value)
+                })
+        })
+    });
+    group.bench_with_input(
+        BenchmarkId::new("felt252", N_CELLS),
+        &values,
+        |b, values| {
+            let (z, alpha) = (Felt252::from(z), Felt252::from(alpha));
+            b.iter(|| {
+                let prod = values
+                    .iter()
+                    .enumerate()
+                    .fold(Felt252::ONE, |prod, (i, value)| {
+                        prod * (z - (Felt252::from(i as u64) + alpha * Felt252::from(*value)))
+                    });
+                U256::from(black_box(prod))
+            })
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(benches, bench_montgomery_encode, bench_page_product);
+criterion_main!(benches);
@@ -1,19 +1,18 @@
 use ethers::{
-    contract::ContractError,
     core::k256::ecdsa::SigningKey,
     middleware::SignerMiddleware,
     providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer, Wallet},
-    types::{Address, U64},
+    signers::{LocalWallet, Signer},
     utils::{hex, Anvil},
 };
 use stark_evm_adapter::{
     annotated_proof::AnnotatedProof,
     annotation_parser::{split_fri_merkle_statements, SplitProofs},
+    network::NetworkConfig,
     oods_statement::FactTopology,
-    ContractFunctionCall,
+    submission::Pipeline,
 };
-use std::{convert::TryFrom, env, fs::read_to_string, str::FromStr, sync::Arc};
+use std::{collections::HashMap, convert::TryFrom, env, fs::read_to_string, sync::Arc};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -30,8 +29,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut anvil = None;
 
-    let provider: Provider<Http> = if url.is_ok() {
-        Provider::try_from(url.unwrap().as_str())?
+    let provider: Provider<Http> = if let Ok(url) = url {
+        Provider::try_from(url.as_str())?
     } else {
         let url = fork_url.unwrap();
         anvil = Some(Anvil::new().fork(url).block_time(1u8).spawn());
@@ -65,98 +64,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // generate split proofs
     let split_proofs: SplitProofs = split_fri_merkle_statements(annotated_proof.clone()).unwrap();
 
-    let topologies_file = read_to_string(env::var("FACT_TOPOLOGIES")?)?;
-    let topology_json: serde_json::Value = serde_json::from_str(&topologies_file).unwrap();
-
-    let fact_topologies: Vec<FactTopology> =
-        serde_json::from_value(topology_json.get("fact_topologies").unwrap().clone()).unwrap();
-
-    // start verifying all split proofs
-    println!("Verifying trace decommitments:");
-    let contract_address = Address::from_str("0x634dcf4f1421fc4d95a968a559a450ad0245804c").unwrap();
-    for i in 0..split_proofs.merkle_statements.len() {
-        let key = format!("Trace {}", i);
-        let trace_merkle = split_proofs.merkle_statements.get(&key).unwrap();
-
-        let call = trace_merkle.verify(contract_address, signer.clone());
-        assert_call(call, &key).await?;
-    }
-
-    println!("Verifying FRI decommitments:");
-    let contract_address = Address::from_str("0xdef8a3b280a54ee7ed4f72e1c7d6098ad8df44fb").unwrap();
-    for (i, fri_statement) in split_proofs.fri_merkle_statements.iter().enumerate() {
-        let call = fri_statement.verify(contract_address, signer.clone());
-
-        assert_call(call, &format!("FRI statement: {}", i)).await?;
-    }
-
-    let (_, continuous_pages) = split_proofs.main_proof.memory_page_registration_args();
-
-    let memory_fact_registry_address =
-        Address::from_str("0x40864568f679c10ac9e72211500096a5130770fa").unwrap();
-
-    for (index, page) in continuous_pages.iter().enumerate() {
-        let register_continuous_pages_call =
-            split_proofs.main_proof.register_continuous_memory_page(
-                memory_fact_registry_address,
-                signer.clone(),
-                page.clone(),
-            );
-
-        let name = format!("register continuous page: {}", index);
-
-        assert_call(register_continuous_pages_call, &name).await?;
-    }
-
-    println!("Verifying main proof:");
-    let contract_address = Address::from_str("0xd51a3d50d4d2f99a345a66971e650eea064dd8df").unwrap();
-
-    let task_metadata = split_proofs
-        .main_proof
-        .generate_tasks_metadata(true, fact_topologies)
-        .unwrap();
-
-    let call = split_proofs
-        .main_proof
-        .verify(contract_address, signer, task_metadata);
-
-    assert_call(call, "Main proof").await?;
+    // use the fact topologies written by the bootloader runner if given, otherwise derive them
+    // from the public memory
+    let fact_topologies: Vec<FactTopology> = match env::var("FACT_TOPOLOGIES") {
+        Ok(path) => {
+            let topologies_file = read_to_string(path)?;
+            let topology_json: serde_json::Value = serde_json::from_str(&topologies_file).unwrap();
+            serde_json::from_value(topology_json.get("fact_topologies").unwrap().clone()).unwrap()
+        }
+        Err(_) => annotated_proof
+            .public_input
+            .fact_topologies(true, &HashMap::new())?,
+    };
 
-    Ok(())
-}
+    // check the merkle and FRI statements offline before spending gas on them
+    split_proofs.verify_locally()?;
 
-async fn assert_call(
-    call: ContractFunctionCall,
-    name: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    match call.send().await {
-        Ok(pending_tx) => match pending_tx.await {
-            Ok(mined_tx) => {
-                let tx_receipt = mined_tx.unwrap();
-                if tx_receipt.status.unwrap_or_default() == U64::from(1) {
-                    println!("Verified: {}", name);
-                    Ok(())
-                } else {
-                    Err(format!("Transaction failed: {}, but did not revert.", name).into())
-                }
-            }
-            Err(e) => Err(decode_revert_message(e.into()).into()),
-        },
-        Err(e) => {
-            Err(decode_revert_message(e).into())
-            // Err(e.into())
+    // submit all split proofs, resuming from the state file if a previous run stopped early
+    // the verifier contracts from NETWORK_CONFIG if set, otherwise the built-in NETWORK profile
+    let network = match env::var("NETWORK_CONFIG") {
+        Ok(path) => NetworkConfig::load(path)?,
+        Err(_) => env::var("NETWORK")
+            .unwrap_or("mainnet".to_string())
+            .parse()?,
+    };
+    println!("Submitting to {}", network);
+    let state_file = env::var("STATE_FILE").unwrap_or("submission_state.json".to_string());
+    let mut pipeline = Pipeline::new(split_proofs, fact_topologies, network.contracts, state_file)?;
+    pipeline.chain_id = Some(network.chain_id);
+
+    let state = pipeline.run(signer).await?;
+    for record in state.steps {
+        match record.tx_hash {
+            Some(tx_hash) => println!("Verified: {} (tx {:?})", record.step, tx_hash),
+            None => println!("Skipped: {} (already registered)", record.step),
         }
-    }
-}
-
-fn decode_revert_message(
-    e: ContractError<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
-) -> String {
-    match e {
-        ContractError::Revert(err) => {
-            println!("Revert data: {:?}", err.0);
-            err.to_string()
+        for tx_hash in record.replaced {
+            println!("  replaces dropped tx {:?}", tx_hash);
         }
-        _ => format!("Transaction failed: {:?}", e),
     }
+
+    Ok(())
 }
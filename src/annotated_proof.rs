@@ -1,9 +1,22 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
 
-use ethers::types::U256;
-use regex::Regex;
+use ethers::{types::U256, utils::hex};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    annotation::{parse_annotations, Annotation, Direction},
+    errors::{
+        AdapterError, ParseError, ProofParametersError, ProofStructureError, PublicInputError,
+    },
+    layout::Layout,
+    oods_statement::{FactTopology, MainProof},
+};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 /// [AnnotatedProof] maps annotated proof json file which contains the original proof
 /// and the annotations generated by verifier of stone-prover
@@ -13,6 +26,32 @@ pub struct AnnotatedProof {
     pub extra_annotations: Vec<String>,
     pub proof_parameters: ProofParameters,
     pub public_input: PublicInput,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_input: Option<PrivateInput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prover_config: Option<ProverConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<ProofVersion>,
+    /// Fields of the stone proof file that the adapter does not know, kept so that they are
+    /// written back out unchanged.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The proof file written by `cpu_air_prover --out_file`.
+#[derive(Deserialize)]
+pub(crate) struct StoneProof {
+    pub(crate) proof_hex: String,
+    pub(crate) proof_parameters: ProofParameters,
+    pub(crate) public_input: PublicInput,
+    #[serde(default)]
+    pub(crate) private_input: Option<PrivateInput>,
+    #[serde(default)]
+    pub(crate) prover_config: Option<ProverConfig>,
+    #[serde(default)]
+    pub(crate) version: Option<ProofVersion>,
+    #[serde(flatten)]
+    pub(crate) other: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,6 +61,19 @@ pub struct ProofParameters {
     pub use_extension_field: bool,
 }
 
+impl ProofParameters {
+    /// Checks that the proof is over the base field. The EVM verifier and its statement
+    /// contracts only work with base field elements, so the annotations of a proof generated
+    /// with `use_extension_field`, whose field elements are pairs of coordinates, are rejected
+    /// before they are parsed.
+    pub fn check_field(&self) -> Result<(), ProofParametersError> {
+        if self.use_extension_field {
+            return Err(ProofParametersError::ExtensionField);
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StarkParameters {
     pub fri: FriParameters,
@@ -36,6 +88,111 @@ pub struct FriParameters {
     pub proof_of_work_bits: u32,
 }
 
+/// Private input of the prover: where the trace and memory of the execution were read from, and
+/// the inputs of every builtin instance.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "PrivateInputFields", into = "PrivateInputFields")]
+pub struct PrivateInput {
+    pub trace_path: String,
+    pub memory_path: String,
+    /// Inputs of the builtin instances by builtin name, such as `pedersen` or `range_check`.
+    pub builtins: BTreeMap<String, Vec<BuiltinInput>>,
+    /// Fields that are not a list of builtin inputs, kept so that they are written back out
+    /// unchanged.
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+/// The JSON layout of [PrivateInput], in which the builtins are mixed with any other field.
+#[derive(Serialize, Deserialize)]
+struct PrivateInputFields {
+    trace_path: String,
+    memory_path: String,
+    #[serde(flatten)]
+    fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl From<PrivateInputFields> for PrivateInput {
+    fn from(input: PrivateInputFields) -> Self {
+        let mut builtins = BTreeMap::new();
+        let mut other = BTreeMap::new();
+        for (name, value) in input.fields {
+            match serde_json::from_value::<Vec<BuiltinInput>>(value.clone()) {
+                Ok(instances) => {
+                    builtins.insert(name, instances);
+                }
+                Err(_) => {
+                    other.insert(name, value);
+                }
+            }
+        }
+        PrivateInput {
+            trace_path: input.trace_path,
+            memory_path: input.memory_path,
+            builtins,
+            other,
+        }
+    }
+}
+
+impl From<PrivateInput> for PrivateInputFields {
+    fn from(input: PrivateInput) -> Self {
+        let mut fields = input.other;
+        for (name, instances) in input.builtins {
+            // A builtin list always serializes.
+            fields.insert(name, serde_json::to_value(instances).unwrap());
+        }
+        PrivateInputFields {
+            trace_path: input.trace_path,
+            memory_path: input.memory_path,
+            fields,
+        }
+    }
+}
+
+/// Inputs of one builtin instance, such as `{"index": 0, "x": "0x..", "y": "0x.."}` for
+/// `pedersen`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BuiltinInput {
+    pub index: u64,
+    #[serde(flatten)]
+    pub values: BTreeMap<String, BuiltinValue>,
+}
+
+/// A value of a [BuiltinInput]: a field element, or a group of them such as the
+/// `signature_input` of `ecdsa`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum BuiltinValue {
+    Felt(U256),
+    Group(BTreeMap<String, U256>),
+}
+
+/// Configuration of `cpu_air_prover`, from `--prover_config_file`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProverConfig {
+    pub cached_lde_config: CachedLdeConfig,
+    pub constraint_polynomial_task_size: u64,
+    pub n_out_of_memory_merkle_layers: u64,
+    pub table_prover_n_tasks_per_segment: u64,
+    /// Settings the adapter does not know, kept so that they are written back out unchanged.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CachedLdeConfig {
+    pub store_full_lde: bool,
+    pub use_fft_for_eval: bool,
+}
+
+/// Identifies the statement and the build of the prover that generated the proof.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProofVersion {
+    pub commit_hash: String,
+    pub proof_hash: String,
+    pub statement_name: String,
+}
+
 /// Public input for a cairo execution
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PublicInput {
@@ -45,6 +202,10 @@ pub struct PublicInput {
     pub public_memory: Vec<PublicMemory>,
     pub rc_max: u32,
     pub rc_min: u32,
+    /// Fields the adapter does not use, such as `dynamic_params`, kept so that they are written
+    /// back out unchanged.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Memory segments for cairo builtins
@@ -59,22 +220,266 @@ pub struct MemorySegment {
 pub struct PublicMemory {
     pub address: u32,
     pub page: u32,
-    // todo refactor to u256
-    pub value: String,
+    pub value: U256,
+}
+
+impl PublicInput {
+    /// The [Layout] the proof was generated for.
+    pub fn layout(&self) -> Result<Layout, PublicInputError> {
+        self.layout.parse()
+    }
+
+    /// Derives the fact topology of every task from the bootloader output in the public memory,
+    /// replacing the `fact_topologies.json` written by the cairo-lang bootloader runner.
+    ///
+    /// `include_bootloader_config` selects the output format the same way as in
+    /// [generate_tasks_metadata](crate::oods_statement::MainProof::generate_tasks_metadata):
+    /// `false` for the simple bootloader, `true` for the bootloader, whose output starts with its
+    /// config and in which composite tasks are already unpacked into plain ones.
+    ///
+    /// The page sizes of a task come from the public memory pages its output was registered in,
+    /// which the bootloader numbers consecutively from page 1, leaving the task headers in page 0.
+    /// A task whose output is entirely in page 0 gets the trivial topology, and one whose output
+    /// starts in page 0 before moving on to the next pages has that leading part as its first page. The tree structure of
+    /// a task with more than one page is not part of the proof: it is the `gps_fact_topology`
+    /// attribute of the task program, and must be given in `tree_structures` by task index.
+    pub fn fact_topologies(
+        &self,
+        include_bootloader_config: bool,
+        tree_structures: &HashMap<usize, Vec<u8>>,
+    ) -> Result<Vec<FactTopology>, AdapterError> {
+        MainProof::fact_topologies(self, include_bootloader_config, tree_structures)
+    }
+
+    /// Checks the public input before it is serialized for the verifier: every memory segment
+    /// begins before it stops and no two overlap, `n_steps` is a power of two,
+    /// `rc_min <= rc_max`, no public memory address appears twice, the memory pages are numbered
+    /// contiguously from 0, and the addresses of each page above 0 are contiguous.
+    ///
+    /// Returns every violation at once, in [PublicInputError::Invalid].
+    pub fn validate(&self) -> Result<(), PublicInputError> {
+        let mut violations = vec![];
+
+        let mut segments: Vec<(&String, &MemorySegment)> = self.memory_segments.iter().collect();
+        segments.sort_by_key(|(name, segment)| (segment.begin_addr, name.to_string()));
+        for (name, segment) in &segments {
+            if segment.begin_addr > segment.stop_ptr {
+                violations.push(PublicInputError::InvalidSegment {
+                    name: name.to_string(),
+                    begin_addr: segment.begin_addr,
+                    stop_ptr: segment.stop_ptr,
+                });
+            }
+        }
+        // Empty segments take no memory and cannot overlap.
+        let used: Vec<&(&String, &MemorySegment)> = segments
+            .iter()
+            .filter(|(_, segment)| segment.begin_addr < segment.stop_ptr)
+            .collect();
+        for (i, (first, first_segment)) in used.iter().enumerate() {
+            for (second, second_segment) in &used[i + 1..] {
+                if second_segment.begin_addr >= first_segment.stop_ptr {
+                    break;
+                }
+                violations.push(PublicInputError::OverlappingSegments {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                });
+            }
+        }
+
+        if !self.n_steps.is_power_of_two() {
+            violations.push(PublicInputError::NStepsNotPowerOfTwo {
+                n_steps: self.n_steps,
+            });
+        }
+        if self.rc_min > self.rc_max {
+            violations.push(PublicInputError::InvalidRangeCheckBounds {
+                rc_min: self.rc_min,
+                rc_max: self.rc_max,
+            });
+        }
+
+        let mut addresses = BTreeMap::new();
+        for cell in &self.public_memory {
+            *addresses.entry(cell.address).or_insert(0) += 1;
+        }
+        violations.extend(
+            addresses
+                .into_iter()
+                .filter(|(_, count)| *count > 1)
+                .map(|(address, _)| PublicInputError::DuplicateAddress { address }),
+        );
+
+        let mut pages: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for cell in &self.public_memory {
+            pages.entry(cell.page).or_default().push(cell.address);
+        }
+        match pages.keys().next_back() {
+            None => violations.push(PublicInputError::EmptyPublicMemory),
+            Some(&last_page) => violations.extend(
+                (0..=last_page)
+                    .filter(|page| !pages.contains_key(page))
+                    .map(|page| PublicInputError::MissingPage { page }),
+            ),
+        }
+        for (&page, addresses) in pages.range(1..) {
+            let start = addresses[0];
+            if let Some((_, &address)) = addresses
+                .iter()
+                .enumerate()
+                .find(|(offset, &address)| address as u64 != start as u64 + *offset as u64)
+            {
+                violations.push(PublicInputError::NonContinuousPage {
+                    page,
+                    address: U256::from(address),
+                });
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(PublicInputError::Invalid { violations })
+        }
+    }
 }
 
 impl AnnotatedProof {
-    pub fn extract_interaction_elements(&self) -> (U256, U256) {
-        let re = Regex::new(r"V->P: /cpu air/STARK/Interaction: Interaction element #\d+: Field Element\(0x([0-9a-f]+)\)").unwrap();
-        let annotations = self.annotations.join("\n");
+    /// Merges the three outputs of the stone prover into an [AnnotatedProof]:
+    /// the proof written by `cpu_air_prover --out_file`, and the annotations written by
+    /// `cpu_air_verifier --annotation-file` and `--extra-output-file`.
+    pub fn from_stone_files(
+        proof_json: impl AsRef<Path>,
+        annotation_txt: impl AsRef<Path>,
+        extra_annotation_txt: impl AsRef<Path>,
+    ) -> Result<AnnotatedProof, AdapterError> {
+        let open = |path: &Path| {
+            File::open(path).map_err(|source| AdapterError::File {
+                path: path.display().to_string(),
+                source,
+            })
+        };
+        Self::from_readers(
+            open(proof_json.as_ref())?,
+            open(annotation_txt.as_ref())?,
+            open(extra_annotation_txt.as_ref())?,
+        )
+    }
 
-        let interaction_elements: Vec<U256> = re
-            .captures_iter(&annotations)
-            .filter_map(|cap| U256::from_str_radix(&cap[1], 16).ok())
+    /// Same as [AnnotatedProof::from_stone_files], reading from arbitrary readers.
+    ///
+    /// Fails if the proof is not exactly as long as the annotated proof bytes, or if the extra
+    /// annotations reference a merkle tree that is not decommitted in the annotations.
+    pub fn from_readers(
+        proof_json: impl Read,
+        annotation_txt: impl Read,
+        extra_annotation_txt: impl Read,
+    ) -> Result<AnnotatedProof, AdapterError> {
+        let proof: StoneProof =
+            serde_json::from_reader(BufReader::new(proof_json)).map_err(ParseError::from)?;
+
+        let annotated_proof = AnnotatedProof {
+            proof_hex: proof.proof_hex,
+            annotations: read_lines(annotation_txt)?,
+            extra_annotations: read_lines(extra_annotation_txt)?,
+            proof_parameters: proof.proof_parameters,
+            public_input: proof.public_input,
+            private_input: proof.private_input,
+            prover_config: proof.prover_config,
+            version: proof.version,
+            other: proof.other,
+        };
+        annotated_proof.check_annotations()?;
+        Ok(annotated_proof)
+    }
+
+    /// Checks that the annotations describe this proof: their `P->V` offsets end exactly at the
+    /// end of the proof, and every merkle tree in the extra annotations is decommitted in the
+    /// annotations.
+    fn check_annotations(&self) -> Result<(), AdapterError> {
+        self.proof_parameters.check_field()?;
+        let proof_len = hex::decode(&self.proof_hex)
+            .map_err(ParseError::from)?
+            .len();
+        let annotations = parse_annotations(&self.annotations, "annotations")?;
+        let extra_annotations = parse_annotations(&self.extra_annotations, "extra annotations")?;
+
+        let annotated_len = annotations
+            .iter()
+            .filter_map(|annotation| annotation.proof_range())
+            .map(|range| range.end)
+            .max()
+            .unwrap_or(0);
+        if annotated_len != proof_len {
+            return Err(ProofStructureError::ProofLengthMismatch {
+                proof_len,
+                annotated_len,
+            }
+            .into());
+        }
+
+        let merkle_names = |annotations: &[Annotation]| -> BTreeSet<String> {
+            annotations
+                .iter()
+                .filter(|annotation| {
+                    matches!(
+                        annotation,
+                        Annotation::DecommitmentNode { .. } | Annotation::DecommitmentData { .. }
+                    )
+                })
+                .filter_map(|annotation| annotation.name().map(str::to_string))
+                .collect()
+        };
+        let known = merkle_names(&annotations);
+        let unknown: Vec<String> = merkle_names(&extra_annotations)
+            .difference(&known)
+            .cloned()
             .collect();
+        if !unknown.is_empty() {
+            return Err(ProofStructureError::UnknownExtraMerkles { names: unknown }.into());
+        }
+        Ok(())
+    }
 
-        assert!(interaction_elements.len() == 3 || interaction_elements.len() == 6);
+    /// Returns the interaction elements `(z, alpha)` drawn by the verifier for the memory pages.
+    pub fn extract_interaction_elements(&self) -> Result<(U256, U256), AdapterError> {
+        interaction_elements(&parse_annotations(&self.annotations, "annotations")?)
+    }
+}
+
+fn read_lines(reader: impl Read) -> Result<Vec<String>, ParseError> {
+    Ok(BufReader::new(reader)
+        .lines()
+        .collect::<Result<Vec<String>, std::io::Error>>()?)
+}
 
-        (interaction_elements[0], interaction_elements[1])
+/// Returns the first two of the `Interaction element` annotations, which are `z` and `alpha`.
+pub(crate) fn interaction_elements(
+    annotations: &[Annotation],
+) -> Result<(U256, U256), AdapterError> {
+    let interaction_elements: Vec<U256> = annotations
+        .iter()
+        .filter_map(|annotation| match annotation {
+            Annotation::InteractionElement {
+                direction: Some(Direction::VerifierToProver),
+                value,
+                ..
+            } => Some(*value),
+            _ => None,
+        })
+        .collect();
+    z_and_alpha(&interaction_elements)
+}
+
+/// Returns `z` and `alpha` out of the `V->P` interaction elements of the proof.
+pub(crate) fn z_and_alpha(interaction_elements: &[U256]) -> Result<(U256, U256), AdapterError> {
+    if interaction_elements.len() != 3 && interaction_elements.len() != 6 {
+        return Err(ProofStructureError::InteractionElementCount {
+            found: interaction_elements.len(),
+        }
+        .into());
     }
+
+    Ok((interaction_elements[0], interaction_elements[1]))
 }
@@ -0,0 +1,399 @@
+use std::{ops::Range, str::FromStr};
+
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ParseError;
+
+/// Which way the annotated data travels in the interactive protocol.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// `P->V[start:end]`: sent by the prover, stored at bytes `start..end` of the proof.
+    ProverToVerifier { start: usize, end: usize },
+    /// `V->P`: randomness drawn by the verifier, not part of the proof bytes.
+    VerifierToProver,
+}
+
+impl Direction {
+    /// The proof bytes this annotation covers, if it was sent by the prover.
+    pub fn proof_range(&self) -> Option<Range<usize>> {
+        match *self {
+            Direction::ProverToVerifier { start, end } => Some(start..end),
+            Direction::VerifierToProver => None,
+        }
+    }
+}
+
+/// A single line of the annotations printed by the stone verifier (`--annotation-file`) or of its
+/// extra annotations (`--extra-output-file`).
+///
+/// Every variant but [Annotation::Comment] carries the [Direction] of the line (`None` for extra
+/// annotations, which have no prefix) and its `/`-separated path, e.g.
+/// `["cpu air", "STARK", "FRI", "Decommitment", "Layer 1"]`.
+///
+/// Example:
+/// `P->V[8768:8800]: /cpu air/STARK/FRI/Commitment/Layer 1: Commitment: Hash(0x9945...)`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Annotation {
+    /// `Commitment: Hash(..)`, the root of a merkle tree.
+    Commitment {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        hash: U256,
+    },
+    /// `For node N: Hash(..)`, an authentication node of a merkle decommitment.
+    DecommitmentNode {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        node: U256,
+        hash: U256,
+    },
+    /// `To complete packages, element #N: Data(..)`, a leaf of a single column trace.
+    DecommitmentData {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        node: U256,
+        data: U256,
+    },
+    /// `Row R, Column C: Field Element(..)` under a `Virtual Oracle`, a decommitted trace cell.
+    TraceElement {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        row: usize,
+        column: usize,
+        value: U256,
+    },
+    /// `Row R, Column C: Field Element(..)` of a FRI layer.
+    FriElement {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        row: usize,
+        column: usize,
+        value: U256,
+    },
+    /// `xInv for index N: Field Element(..)`, the inverse of the evaluation point of a FRI query.
+    XInv {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        index: usize,
+        value: U256,
+    },
+    /// `Interaction element #N: Field Element(..)`
+    InteractionElement {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        index: usize,
+        value: U256,
+    },
+    /// `Constraint polynomial random element: Field Element(..)`
+    ConstraintRandomElement {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        value: U256,
+    },
+    /// `Evaluation point: Field Element(..)`, of the OODS or of a FRI layer.
+    EvaluationPoint {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        value: U256,
+    },
+    /// `N: Field Element(..)` under `OODS values`.
+    OodsValue {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        index: usize,
+        value: U256,
+    },
+    /// `N: Number(Q)` under `QueryIndices`.
+    QueryIndex {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        index: usize,
+        query: u64,
+    },
+    /// `Coefficients: Field Elements(..)` of the FRI last layer.
+    LastLayerCoefficients {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        values: Vec<U256>,
+    },
+    /// `POW: Data(..)`, the proof of work nonce.
+    ProofOfWorkNonce {
+        direction: Option<Direction>,
+        path: Vec<String>,
+        nonce: U256,
+    },
+    /// Lines carrying no data: the protocol title, blank lines and the proof statistics footer.
+    Comment { text: String },
+}
+
+/// The value printed at the end of an annotation line, e.g. `Hash(0x..)`.
+enum Value {
+    Hash(U256),
+    FieldElement(U256),
+    FieldElements(Vec<U256>),
+    Data(U256),
+    Number(u64),
+}
+
+impl Annotation {
+    /// Direction of the line, `None` for extra annotations and comments.
+    pub fn direction(&self) -> Option<Direction> {
+        match self {
+            Annotation::Commitment { direction, .. }
+            | Annotation::DecommitmentNode { direction, .. }
+            | Annotation::DecommitmentData { direction, .. }
+            | Annotation::TraceElement { direction, .. }
+            | Annotation::FriElement { direction, .. }
+            | Annotation::XInv { direction, .. }
+            | Annotation::InteractionElement { direction, .. }
+            | Annotation::ConstraintRandomElement { direction, .. }
+            | Annotation::EvaluationPoint { direction, .. }
+            | Annotation::OodsValue { direction, .. }
+            | Annotation::QueryIndex { direction, .. }
+            | Annotation::LastLayerCoefficients { direction, .. }
+            | Annotation::ProofOfWorkNonce { direction, .. } => *direction,
+            Annotation::Comment { .. } => None,
+        }
+    }
+
+    /// Path segments of the line, empty for comments.
+    pub fn path(&self) -> &[String] {
+        match self {
+            Annotation::Commitment { path, .. }
+            | Annotation::DecommitmentNode { path, .. }
+            | Annotation::DecommitmentData { path, .. }
+            | Annotation::TraceElement { path, .. }
+            | Annotation::FriElement { path, .. }
+            | Annotation::XInv { path, .. }
+            | Annotation::InteractionElement { path, .. }
+            | Annotation::ConstraintRandomElement { path, .. }
+            | Annotation::EvaluationPoint { path, .. }
+            | Annotation::OodsValue { path, .. }
+            | Annotation::QueryIndex { path, .. }
+            | Annotation::LastLayerCoefficients { path, .. }
+            | Annotation::ProofOfWorkNonce { path, .. } => path,
+            Annotation::Comment { .. } => &[],
+        }
+    }
+
+    /// Last path segment, which names the merkle tree or FRI layer, e.g. `Trace 0` or `Layer 1`.
+    pub fn name(&self) -> Option<&str> {
+        self.path().last().map(String::as_str)
+    }
+
+    /// The proof bytes this annotation covers, if it was sent by the prover.
+    pub fn proof_range(&self) -> Option<Range<usize>> {
+        self.direction()
+            .and_then(|direction| direction.proof_range())
+    }
+}
+
+impl FromStr for Annotation {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        if is_comment(line) {
+            return Ok(Annotation::Comment {
+                text: line.to_string(),
+            });
+        }
+        let unknown = || ParseError::UnknownAnnotation(line.to_string());
+
+        let (direction, rest) = parse_direction(line)?;
+        let (path, body) = rest
+            .strip_prefix('/')
+            .and_then(|rest| rest.split_once(": "))
+            .ok_or_else(unknown)?;
+        let path: Vec<String> = path.split('/').map(str::to_string).collect();
+        let (label, value) = body.split_once(": ").ok_or_else(unknown)?;
+        let value = parse_value(value)?;
+        let in_virtual_oracle = path.iter().any(|segment| segment == "Virtual Oracle");
+        let parent = path.last().map(String::as_str);
+
+        let annotation = match (label, value) {
+            ("Commitment", Value::Hash(hash)) => Annotation::Commitment {
+                direction,
+                path,
+                hash,
+            },
+            ("Constraint polynomial random element", Value::FieldElement(value)) => {
+                Annotation::ConstraintRandomElement {
+                    direction,
+                    path,
+                    value,
+                }
+            }
+            ("Evaluation point", Value::FieldElement(value)) => Annotation::EvaluationPoint {
+                direction,
+                path,
+                value,
+            },
+            ("Coefficients", Value::FieldElements(values)) => Annotation::LastLayerCoefficients {
+                direction,
+                path,
+                values,
+            },
+            ("POW", Value::Data(nonce)) => Annotation::ProofOfWorkNonce {
+                direction,
+                path,
+                nonce,
+            },
+            (label, Value::Hash(hash)) if label.starts_with("For node ") => {
+                Annotation::DecommitmentNode {
+                    direction,
+                    path,
+                    node: U256::from_dec_str(&label["For node ".len()..])?,
+                    hash,
+                }
+            }
+            (label, Value::Data(data)) if label.starts_with("To complete packages, element #") => {
+                Annotation::DecommitmentData {
+                    direction,
+                    path,
+                    node: U256::from_dec_str(&label["To complete packages, element #".len()..])?,
+                    data,
+                }
+            }
+            (label, Value::FieldElement(value)) if label.starts_with("Row ") => {
+                let (row, column) = label["Row ".len()..]
+                    .split_once(", Column ")
+                    .ok_or_else(unknown)?;
+                let (row, column) = (row.parse()?, column.parse()?);
+                if in_virtual_oracle {
+                    Annotation::TraceElement {
+                        direction,
+                        path,
+                        row,
+                        column,
+                        value,
+                    }
+                } else {
+                    Annotation::FriElement {
+                        direction,
+                        path,
+                        row,
+                        column,
+                        value,
+                    }
+                }
+            }
+            (label, Value::FieldElement(value)) if label.starts_with("xInv for index ") => {
+                Annotation::XInv {
+                    direction,
+                    path,
+                    index: label["xInv for index ".len()..].parse()?,
+                    value,
+                }
+            }
+            (label, Value::FieldElement(value)) if label.starts_with("Interaction element #") => {
+                Annotation::InteractionElement {
+                    direction,
+                    path,
+                    index: label["Interaction element #".len()..].parse()?,
+                    value,
+                }
+            }
+            (label, Value::FieldElement(value)) if parent == Some("OODS values") => {
+                Annotation::OodsValue {
+                    direction,
+                    path,
+                    index: label.parse()?,
+                    value,
+                }
+            }
+            (label, Value::Number(query)) if parent == Some("QueryIndices") => {
+                Annotation::QueryIndex {
+                    direction,
+                    path,
+                    index: label.parse()?,
+                    query,
+                }
+            }
+            _ => return Err(unknown()),
+        };
+        Ok(annotation)
+    }
+}
+
+/// Parses every line of an annotation file, attaching the 1-based line number and `section` to
+/// the error of the first line that cannot be parsed. The result has one entry per line.
+pub fn parse_annotations<S: AsRef<str>>(
+    lines: &[S],
+    section: &'static str,
+) -> Result<Vec<Annotation>, ParseError> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| {
+            line.as_ref()
+                .parse()
+                .map_err(|e: ParseError| e.at_line(section, idx + 1))
+        })
+        .collect()
+}
+
+/// Example:
+/// title cpu air Proof Protocol
+/// Proof Statistics:
+/// Byte count: 159976
+fn is_comment(line: &str) -> bool {
+    let is_statistic = line.split_once(" count: ").is_some_and(|(name, count)| {
+        !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphabetic() || c == ' ')
+            && !count.is_empty()
+            && count.chars().all(|c| c.is_ascii_digit())
+    });
+    line.trim().is_empty()
+        || line.starts_with("title ")
+        || line == "Proof Statistics:"
+        || is_statistic
+}
+
+/// Splits the `P->V[start:end]: ` or `V->P: ` prefix off a line.
+fn parse_direction(line: &str) -> Result<(Option<Direction>, &str), ParseError> {
+    if let Some(rest) = line.strip_prefix("V->P: ") {
+        return Ok((Some(Direction::VerifierToProver), rest));
+    }
+    let Some(rest) = line.strip_prefix("P->V[") else {
+        return Ok((None, line));
+    };
+    let (range, rest) = rest
+        .split_once("]: ")
+        .ok_or(ParseError::InvalidLineFormat)?;
+    let (start, end) = range.split_once(':').ok_or(ParseError::InvalidLineFormat)?;
+    let direction = Direction::ProverToVerifier {
+        start: start.parse()?,
+        end: end.parse()?,
+    };
+    Ok((Some(direction), rest))
+}
+
+/// Parses values such as `Hash(0x..)`, `Field Element(0x..)`, `Field Elements(0x.., 0x..)`,
+/// `Data(0x..)` and `Number(42)`.
+fn parse_value(value: &str) -> Result<Value, ParseError> {
+    let (kind, inner) = value
+        .strip_suffix(')')
+        .and_then(|value| value.split_once('('))
+        .ok_or_else(|| ParseError::UnknownAnnotation(value.to_string()))?;
+    Ok(match kind {
+        "Hash" => Value::Hash(parse_hex(inner)?),
+        "Field Element" => Value::FieldElement(parse_hex(inner)?),
+        "Field Elements" => Value::FieldElements(
+            inner
+                .split(", ")
+                .map(parse_hex)
+                .collect::<Result<Vec<U256>, ParseError>>()?,
+        ),
+        "Data" => Value::Data(parse_hex(inner)?),
+        "Number" => Value::Number(inner.parse()?),
+        _ => return Err(ParseError::UnknownAnnotation(value.to_string())),
+    })
+}
+
+fn parse_hex(value: &str) -> Result<U256, ParseError> {
+    let hex = value
+        .strip_prefix("0x")
+        .ok_or(ParseError::HexExtractionError)?;
+    Ok(U256::from_str_radix(hex, 16)?)
+}
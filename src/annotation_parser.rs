@@ -1,14 +1,20 @@
 use ethers::abi::Token;
 use ethers::utils::keccak256;
 use ethers::{types::U256, utils::hex};
-use num_bigint::BigUint;
-use num_traits::{Num, One};
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-
-use crate::annotated_proof::AnnotatedProof;
-use crate::errors::ParseError;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::annotated_proof::{
+    z_and_alpha, AnnotatedProof, ProofParameters, PublicInput, StoneProof,
+};
+use crate::annotation::{Annotation, Direction};
+use crate::errors::{
+    AdapterError, ParseError, ProofParametersError, ProofStructureError, StatementVerificationError,
+};
+use crate::field::Felt252;
 use crate::fri_merkle_statement::FRIMerkleStatement;
 use crate::merkle_statement::MerkleStatement;
 use crate::oods_statement::MainProof;
@@ -17,8 +23,7 @@ use crate::oods_statement::MainProof;
 struct MerkleLine {
     pub name: String,
     pub node: U256,
-    pub digest: String,
-    pub annotation: String,
+    pub digest: U256,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -26,30 +31,26 @@ struct FriLine {
     pub name: String,
     pub row: usize,
     pub col: usize,
-    pub element: String,
-    pub annotation: String,
+    pub element: U256,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct FriXInvLine {
     pub name: String,
     pub index: usize,
-    pub inv: String,
-    pub annotation: String,
+    pub inv: U256,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct CommitmentLine {
     pub name: String,
-    pub digest: String,
-    pub annotation: String,
+    pub digest: U256,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct EvalPointLine {
     pub name: String,
-    pub point: String,
-    pub annotation: String,
+    pub point: U256,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -60,6 +61,11 @@ struct FriExtras {
 
 type MerkleExtrasDict = HashMap<String, Vec<MerkleLine>>;
 
+/// The annotations of the original proof, collected line by line: a dictionary from Merkle name
+/// to a list of all its decommitment data from the proof, another dictionary from Merkle name
+/// to the commitments, the FRI decommitments, and the trimmed main proof with Merkle
+/// decommitments removed.
+#[derive(Default)]
 struct FriMerklesOriginal {
     pub merkle_originals: MerkleExtrasDict,
     pub merkle_commitments: HashMap<String, CommitmentLine>,
@@ -68,6 +74,17 @@ struct FriMerklesOriginal {
     pub fri_names: Vec<String>,
     pub original_proof: Vec<u8>,
     pub merkle_patches: HashSet<String>,
+    /// Column 0 of the decommitted trace cells by trace name, used by [single_column_merkle_patch].
+    pub trace_first_columns: HashMap<String, Vec<(usize, U256)>>,
+    /// The `V->P` interaction elements, of which the first two are `z` and `alpha`.
+    pub interaction_elements: Vec<U256>,
+    /// End of the last `P->V` range, which is the length of the annotated proof.
+    pub annotated_len: usize,
+    /// The queries to the evaluation domain, with repetitions.
+    pub queries: Vec<u64>,
+    /// Number of coefficients of the FRI last layer.
+    pub last_layer_coefficients: Option<usize>,
+    trace_commitment_counter: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -78,271 +95,71 @@ pub struct SplitProofs {
     pub fri_merkle_statements: Vec<FRIMerkleStatement>,
 }
 
-// Parses hex strings and pads with zeros to make it 64 characters long
-fn extract_hex(line: &str) -> Result<String, ParseError> {
-    let re = Regex::new(r"\(0x([0-9a-f]+)\)")?;
-    Ok(re
-        .captures(line)
-        .and_then(|cap| cap.get(1))
-        .map_or_else(String::new, |m| format!("{:0>64}", m.as_str())))
-}
-
-/// Example:
-/// /cpu air/STARK/FRI/Decommitment/Layer 0/Virtual Oracle/Trace 0: For node 18762888: Hash(0x0000000000000000000000006671d703e83592b3b9d60eb1f91e8e0b8561f3d1)
-fn is_merkle_line(line: &str) -> bool {
-    line.contains("Decommitment") && line.contains("node") && line.contains("Hash")
-}
-
-/// Parses a proof annotation line which is part of a Merkle decommitment, and returns the name
-/// of the Merkle, the node provided, and the hash digest.
-fn parse_merkle_line(line: &str) -> Result<MerkleLine, ParseError> {
-    let name = line
-        .split('/')
-        .last()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .to_string();
-
-    let node_str = line
-        .split("node ")
-        .nth(1)
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?;
-
-    let node: U256 = U256::from_dec_str(node_str)?;
-
-    let digest = extract_hex(line)?;
-
-    Ok(MerkleLine {
-        name,
-        node,
-        digest,
-        annotation: line.to_string(),
-    })
-}
-
-fn is_merkle_data_line(line: &str) -> bool {
-    line.contains("Decommitment") && line.contains("element #") && line.contains("Data")
-}
-
-/// Parses a proof annotation line which is part of a Merkle decommitment as a package completion
-/// line, and returns the name of the Merkle, the node provided, and the data element.
-fn parse_merkle_data_line(line: &str) -> Result<MerkleLine, ParseError> {
-    let name = line
-        .split('/')
-        .last()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .to_string();
-
-    let node_str = line
-        .split("element #")
-        .nth(1)
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?;
-
-    let node: U256 = U256::from_dec_str(node_str)?;
-
-    let digest = extract_hex(line)?;
-
-    Ok(MerkleLine {
-        name,
-        node,
-        digest,
-        annotation: line.to_string(),
-    })
-}
-
-/// Example:
-/// /cpu air/STARK/FRI/Decommitment/Layer 4: Row 242, Column 3: Field Element(0x32bb09aed5ade9cadd9c0e76f261422cb1ef1f483f28cfe06b81d416defde66)
-fn is_fri_line(line: &str) -> bool {
-    line.contains("Decommitment")
-        && line.contains("Row")
-        && line.contains("Field Element")
-        && !line.contains("Virtual Oracle")
-}
-
-/// Parses a proof annotation line which is part of a FRI decommitment, and returns the name
-/// of the FRI, the cell provided, and the field element.
-fn parse_fri_line(line: &str) -> Result<FriLine, ParseError> {
-    let parts: Vec<&str> = line.split('/').collect();
-    let name = parts
-        .last()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .to_string();
-
-    let row_col_part = line
-        .split(':')
-        .nth_back(1)
-        .ok_or(ParseError::InvalidLineFormat)?;
-    let row_col: Vec<&str> = row_col_part.split(',').collect();
-    if row_col.len() != 2 {
-        return Err(ParseError::InvalidLineFormat);
+impl SplitProofs {
+    /// Checks every merkle and FRI statement offline, the way the on-chain registries would,
+    /// so that a bad split is rejected before any transaction is sent.
+    pub fn verify_locally(&self) -> Result<(), StatementVerificationError> {
+        let mut names: Vec<&String> = self.merkle_statements.keys().collect();
+        names.sort();
+        for name in names {
+            self.merkle_statements[name]
+                .verify_locally()
+                .map_err(|source| StatementVerificationError::Merkle {
+                    name: name.clone(),
+                    source: Box::new(source),
+                })?;
+        }
+        for (layer, statement) in self.fri_merkle_statements.iter().enumerate() {
+            statement
+                .verify_locally()
+                .map_err(|source| StatementVerificationError::Fri {
+                    layer,
+                    source: Box::new(source),
+                })?;
+        }
+        Ok(())
     }
-    let row = row_col[0]
-        .split_whitespace()
-        .nth(1)
-        .ok_or(ParseError::InvalidLineFormat)?
-        .parse::<usize>()?;
-    let col = row_col[1]
-        .split_whitespace()
-        .nth(1)
-        .ok_or(ParseError::InvalidLineFormat)?
-        .parse::<usize>()?;
-
-    let element = extract_hex(line)?;
-
-    Ok(FriLine {
-        name,
-        row,
-        col,
-        element,
-        annotation: line.to_string(),
-    })
 }
 
-/// Example:
-/// /cpu air/STARK/FRI/Decommitment/Last Layer: xInv for index 2030: Field Element(0x6446597268afd0e19853257f3caf5f1d4bd896ae7edeba0047d8c09cf399b7b)
-fn is_fri_xinv_line(line: &str) -> bool {
-    line.contains("Decommitment") && line.contains("xInv") && line.contains("Field Element")
-}
-
-/// Parses a proof extra annotation line which contains the xInv for a FRI query, and returns the
-/// name of the FRI, the index provided, and the xInv.
-fn parse_fri_xinv_line(line: &str) -> Result<FriXInvLine, ParseError> {
-    let parts: Vec<&str> = line.split('/').collect();
-    let name = parts
-        .last()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .to_string();
-
-    let index_str = line
-        .split("index ")
-        .nth(1)
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?;
-
-    let index = index_str
-        .parse::<usize>()
-        .map_err(|_| ParseError::InvalidLineFormat)?;
-
-    let inv = extract_hex(line)?;
-
-    Ok(FriXInvLine {
-        name,
-        index,
-        inv,
-        annotation: line.to_string(),
-    })
-}
-
-/// Example
-/// P->V[23680:23712]: /cpu air/STARK/FRI/Commitment/Layer 4: Commitment: Hash(0x000000000000000000000000ab09e126b366725542268572e3303dcaccf142a5)
-fn is_commitment_line(line: &str) -> bool {
-    line.contains("Commitment") && line.contains("Hash")
-}
-
-/// Parses a proof annotation line of a Merkle commitment, and returns the name
-/// of the Merkle and the hash digest.
+/// Returns the name of the merkle tree committed to by a [Annotation::Commitment].
 /// For "Commit on Trace" lines, the name (index) of the trace is not found inside the line, but
-/// instead is tracked by a global counter.
-fn parse_commitment_line(
-    line: &str,
-    trace_commitment_counter: &mut usize,
-) -> Result<(CommitmentLine, usize), ParseError> {
-    let parts: Vec<&str> = line.split(':').collect();
-    let path_parts: Vec<&str> = parts
-        .get(2)
-        .ok_or(ParseError::InvalidLineFormat)?
-        .trim()
-        .split('/')
-        .collect();
-    let name;
-    let new_trace_commitment_counter;
-
-    if path_parts.last() == Some(&"Commit on Trace") {
-        name = format!("Trace {}", trace_commitment_counter);
-        *trace_commitment_counter += 1;
-        new_trace_commitment_counter = *trace_commitment_counter;
-    } else if path_parts.get(path_parts.len().saturating_sub(2)) == Some(&"Commitment") {
-        name = path_parts
-            .last()
-            .map(|s| s.to_string())
-            .ok_or(ParseError::InvalidLineFormat)?;
-        new_trace_commitment_counter = *trace_commitment_counter;
-    } else {
-        return Err(ParseError::InvalidLineFormat);
+/// instead is tracked by a counter.
+fn commitment_name(path: &[String], trace_commitment_counter: &mut usize) -> Option<String> {
+    match path {
+        [.., last] if last == "Commit on Trace" => {
+            let name = format!("Trace {}", trace_commitment_counter);
+            *trace_commitment_counter += 1;
+            Some(name)
+        }
+        [.., parent, last] if parent == "Commitment" => Some(last.clone()),
+        _ => None,
     }
-
-    let digest = extract_hex(line)?;
-    Ok((
-        CommitmentLine {
-            name,
-            digest,
-            annotation: line.to_string(),
-        },
-        new_trace_commitment_counter,
-    ))
-}
-
-/// Example:
-/// V->P: /cpu air/STARK/FRI/Commitment/Layer 4: Evaluation point: Field Element(0x6afcea9769e097e3d5c3cb8ee22bcc51ed7e8cf8cfa617ee915a6af68736fee)
-fn is_eval_point_line(line: &str) -> bool {
-    line.contains("Evaluation point") && line.contains("Layer")
 }
 
-/// Parses a proof annotation line of an evaluation point submission, returning the layer number
-/// and point.
-fn parse_eval_point_line(line: &str) -> Result<EvalPointLine, ParseError> {
-    let parts: Vec<&str> = line.split('/').collect();
-    let name = parts
-        .last()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .split(':')
-        .next()
-        .ok_or(ParseError::InvalidLineFormat)?
-        .to_string();
-
-    let point = extract_hex(line)?;
-
-    Ok(EvalPointLine {
-        name,
-        point,
-        annotation: line.to_string(),
-    })
-}
-
-/// Parses a proof annotation line, returning the start and end indices of the proof segment
-/// which the line annotates, or (0,0) if the annotation is for verifier to prover "interaction".
-fn line_to_indices(line: &str) -> Result<(usize, usize), ParseError> {
-    if !line.starts_with("P->V[") {
-        Ok((0, 0))
-    } else {
-        let indices_part = &line[5..line.find(']').ok_or(ParseError::InvalidLineFormat)?];
-        let indices: Vec<&str> = indices_part.split(':').collect();
-        if indices.len() != 2 {
-            Err(ParseError::InvalidLineFormat)
-        } else {
-            let start = indices[0].parse::<usize>()?;
-            let end = indices[1].parse::<usize>()?;
-            Ok((start, end))
-        }
+/// Returns the common height of the decommitment nodes of the merkle `name`.
+fn merkle_height(name: &str, mlines: &[MerkleLine]) -> Result<usize, ProofStructureError> {
+    let heights = mlines
+        .iter()
+        .map(|mline| {
+            mline
+                .node
+                .bits()
+                .checked_sub(1)
+                .ok_or(ProofStructureError::InvalidMerkleNode {
+                    name: name.to_string(),
+                    node: mline.node,
+                })
+        })
+        .collect::<Result<HashSet<usize>, ProofStructureError>>()?;
+
+    match heights.len() {
+        0 => Err(ProofStructureError::MissingMerkleExtras {
+            name: name.to_string(),
+        }),
+        1 => Ok(heights.into_iter().next().unwrap_or_default()),
+        _ => Err(ProofStructureError::InconsistentMerkleHeights {
+            name: name.to_string(),
+        }),
     }
 }
 
@@ -353,49 +170,28 @@ fn gen_merkle_statement_call(
     merkle_extras: Vec<MerkleLine>,
     merkle_original: Vec<MerkleLine>,
     merkle_commit: CommitmentLine,
-) -> Result<MerkleStatement, ParseError> {
+) -> Result<MerkleStatement, AdapterError> {
     let qs: Vec<&str> = merkle_extras.iter().map(|n| &n.name[..]).collect();
-    let heights: Vec<usize> = merkle_extras
-        .iter()
-        .map(|mline| mline.node.bits() - 1)
-        .collect();
-
-    if !heights.iter().all(|&h| h == heights[0]) {
-        return Err(ParseError::InvalidLineFormat);
-    }
+    let height = merkle_height(&merkle_commit.name, &merkle_extras)?;
 
-    let root = U256::from_str_radix(&merkle_commit.digest, 16)?;
-    let merkle_queue_values: Vec<U256> = merkle_extras
-        .iter()
-        .map(|mline| Ok(U256::from_str_radix(&mline.digest, 16)?))
-        .collect::<Result<Vec<U256>, ParseError>>()?;
-    let proof: Vec<U256> = merkle_original
-        .iter()
-        .map(|mline| Ok(U256::from_str_radix(&mline.digest, 16)?))
-        .collect::<Result<Vec<U256>, ParseError>>()?;
+    let root = merkle_commit.digest;
+    let merkle_queue_values: Vec<U256> = merkle_extras.iter().map(|mline| mline.digest).collect();
+    let proof: Vec<U256> = merkle_original.iter().map(|mline| mline.digest).collect();
     let merkle_queue_indices: Vec<U256> = merkle_extras.iter().map(|mline| mline.node).collect();
 
     Ok(MerkleStatement::new(
         root,
         qs.len(),
-        heights[0],
+        height,
         merkle_queue_indices,
         merkle_queue_values,
         proof,
     ))
 }
 
-fn montgomery_encode(element: &str) -> Result<U256, ParseError> {
-    let prime = BigUint::from_str_radix(
-        "800000000000011000000000000000000000000000000000000000000000001",
-        16,
-    )?;
-    let num = BigUint::from_str_radix(element, 16)?;
-
-    let r = BigUint::one() << 256; // Use 2^256 as R
-    let encoded: BigUint = (num * r) % prime; // this seems to lost the purpose of montgomery encoding which aims to avoid division
-
-    Ok(U256::from_str_radix(&encoded.to_str_radix(10), 10)?)
+/// Montgomery form of a field element, the way the FRI statement contracts expect layer values.
+fn montgomery_encode(element: &U256) -> U256 {
+    Felt252::from(*element).to_montgomery()
 }
 
 fn interleave<T: Clone>(a: Vec<T>, b: Vec<T>, c: Vec<T>) -> Vec<T> {
@@ -406,6 +202,32 @@ fn interleave<T: Clone>(a: Vec<T>, b: Vec<T>, c: Vec<T>) -> Vec<T> {
         .collect()
 }
 
+/// Returns the step size of the FRI layer `name`, the log2 of the width of its rows.
+fn fri_step_size(
+    name: &str,
+    fri_extras: &FriExtras,
+    fri_original: &[FriLine],
+) -> Result<usize, ProofStructureError> {
+    let mut rows_to_cols: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for fline in fri_extras.values.iter().chain(fri_original) {
+        rows_to_cols.entry(fline.row).or_default().insert(fline.col);
+    }
+    let row_lens: HashSet<usize> = rows_to_cols.values().map(HashSet::len).collect();
+    if row_lens.len() != 1 {
+        return Err(ProofStructureError::InconsistentFriRowWidths {
+            name: name.to_string(),
+        });
+    }
+    let width = row_lens.into_iter().next().unwrap_or_default();
+    if !width.is_power_of_two() {
+        return Err(ProofStructureError::InvalidFriRowWidth {
+            name: name.to_string(),
+            width,
+        });
+    }
+    Ok(width.trailing_zeros() as usize)
+}
+
 /// For a single FRI-Merkle decommitment, processes the information from the non-split proof
 /// and extra data prepared by a verifier, and arranges it to be used as input to the
 /// FRI-Merkle Fact Registry.
@@ -417,28 +239,13 @@ fn gen_fri_merkle_statement_call(
     merkle_extras: Vec<MerkleLine>,
     merkle_commitment: CommitmentLine,
     evaluation_point: EvalPointLine,
-) -> Result<FRIMerkleStatement, ParseError> {
-    let root = U256::from_str_radix(&merkle_commitment.digest, 16)?;
-    let eval_point = U256::from_str_radix(&evaluation_point.point, 16)?;
+) -> Result<FRIMerkleStatement, AdapterError> {
+    let root = merkle_commitment.digest;
+    let eval_point = evaluation_point.point;
 
-    let heights: Vec<usize> = merkle_extras
-        .iter()
-        .map(|mline| mline.node.bits() - 1)
-        .collect();
-    assert_eq!(heights.iter().cloned().collect::<HashSet<_>>().len(), 1);
-    let output_height = heights[0];
+    let output_height = merkle_height(&merkle_commitment.name, &merkle_extras)?;
 
-    let mut rows_to_cols: HashMap<usize, Vec<usize>> = HashMap::new();
-    for fline in fri_extras.values.iter().chain(fri_original.iter()) {
-        rows_to_cols.entry(fline.row).or_default().push(fline.col);
-    }
-    let row_lens: Vec<usize> = rows_to_cols
-        .values()
-        .map(|v| v.iter().cloned().collect::<HashSet<_>>().len())
-        .collect();
-    assert_eq!(row_lens.iter().cloned().collect::<HashSet<_>>().len(), 1);
-
-    let step_size = (row_lens[0] as f64).log2() as usize;
+    let step_size = fri_step_size(&merkle_commitment.name, &fri_extras, &fri_original)?;
     let input_height = output_height + step_size;
 
     let input_layer_queries: Vec<U256> = fri_extras
@@ -453,35 +260,28 @@ fn gen_fri_merkle_statement_call(
         .values
         .iter()
         .map(|fline| montgomery_encode(&fline.element))
-        .collect::<Result<Vec<U256>, ParseError>>()?;
+        .collect();
 
     let output_layer_values: Vec<U256> = fri_extras_next
         .values
         .iter()
         .map(|fline| montgomery_encode(&fline.element))
-        .collect::<Result<Vec<U256>, ParseError>>()?;
+        .collect();
 
-    let input_layer_inverses: Vec<U256> = fri_extras
-        .inverses
-        .iter()
-        .map(|fline| Ok(U256::from_str_radix(&fline.inv, 16)?))
-        .collect::<Result<Vec<U256>, ParseError>>()?;
+    let input_layer_inverses: Vec<U256> =
+        fri_extras.inverses.iter().map(|fline| fline.inv).collect();
 
     let output_layer_inverses: Vec<U256> = fri_extras_next
         .inverses
         .iter()
-        .map(|fline| Ok(U256::from_str_radix(&fline.inv, 16)?))
-        .collect::<Result<Vec<U256>, ParseError>>()?;
+        .map(|fline| fline.inv)
+        .collect();
 
     let proof: Vec<U256> = fri_original
         .iter()
         .map(|fline| montgomery_encode(&fline.element))
-        .chain(
-            merkle_original
-                .iter()
-                .map(|mline| Ok(U256::from_str_radix(&mline.digest, 16)?)),
-        )
-        .collect::<Result<Vec<U256>, ParseError>>()?;
+        .chain(merkle_original.iter().map(|mline| mline.digest))
+        .collect();
 
     let input_interleaved = interleave(
         input_layer_queries.clone(),
@@ -511,124 +311,200 @@ fn gen_fri_merkle_statement_call(
     })
 }
 
-/// Parses the extra annotations, returning a dictionary from Merkle name to a list
-/// of all its extra (initialization) data.
-fn parse_fri_merkles_extra(
-    extra_annot_lines: Vec<&str>,
-) -> Result<(MerkleExtrasDict, Vec<FriExtras>), ParseError> {
-    let mut merkle_extras_dict = MerkleExtrasDict::new();
-    let mut fri_extras_dict = HashMap::new();
-    let mut fri_names = Vec::new();
-
-    for line in extra_annot_lines {
-        if is_merkle_line(line) {
-            let mline = parse_merkle_line(line)?;
-            merkle_extras_dict
-                .entry(mline.name.clone())
-                .or_default()
-                .push(mline);
-        } else if is_fri_line(line) {
-            let fline = parse_fri_line(line)?;
-            if !fri_extras_dict.contains_key(&fline.name) {
-                fri_extras_dict.insert(
-                    fline.name.clone(),
-                    FriExtras {
+/// The extra annotations, collected line by line: a dictionary from Merkle name to a list of all
+/// its extra (initialization) data, and the extra data of each FRI layer.
+#[derive(Default)]
+struct FriMerklesExtra {
+    merkle_extras: MerkleExtrasDict,
+    fri_extras: HashMap<String, FriExtras>,
+    fri_names: Vec<String>,
+}
+
+impl FriMerklesExtra {
+    /// Adds the extra annotation at 0-based line `idx`.
+    fn push(&mut self, idx: usize, annotation: Annotation) -> Result<(), AdapterError> {
+        let name = annotation.name().unwrap_or_default().to_string();
+        match annotation {
+            Annotation::DecommitmentNode { node, hash, .. } => {
+                self.merkle_extras
+                    .entry(name.clone())
+                    .or_default()
+                    .push(MerkleLine {
+                        name,
+                        node,
+                        digest: hash,
+                    });
+            }
+            Annotation::FriElement {
+                row, column, value, ..
+            } => {
+                if !self.fri_extras.contains_key(&name) {
+                    self.fri_names.push(name.clone());
+                }
+                self.fri_extras
+                    .entry(name.clone())
+                    .or_insert_with(|| FriExtras {
                         values: Vec::new(),
                         inverses: Vec::new(),
-                    },
-                );
-                fri_names.push(fline.name.clone());
+                    })
+                    .values
+                    .push(FriLine {
+                        name,
+                        row,
+                        col: column,
+                        element: value,
+                    });
+            }
+            Annotation::XInv { index, value, .. } => {
+                self.fri_extras
+                    .get_mut(&name)
+                    .ok_or_else(|| ProofStructureError::FriInverseWithoutValues {
+                        name: name.clone(),
+                        line: idx + 1,
+                    })?
+                    .inverses
+                    .push(FriXInvLine {
+                        name,
+                        index,
+                        inv: value,
+                    });
             }
-            fri_extras_dict
-                .get_mut(&fline.name)
-                .unwrap()
-                .values
-                .push(fline);
-        } else if is_fri_xinv_line(line) {
-            let fxline = parse_fri_xinv_line(line)?;
-            fri_extras_dict
-                .get_mut(&fxline.name)
-                .unwrap()
-                .inverses
-                .push(fxline);
+            _ => {}
         }
+        Ok(())
     }
-    let fri_extras_list = Vec::from_iter(
-        fri_names
-            .into_iter()
-            .map(|name| fri_extras_dict.remove(&name).unwrap()),
-    );
 
-    Ok((merkle_extras_dict, fri_extras_list))
+    /// Returns the merkle extras and the FRI extras of each layer, in order.
+    fn finish(mut self) -> (MerkleExtrasDict, Vec<FriExtras>) {
+        let fri_extras_list = self
+            .fri_names
+            .into_iter()
+            .filter_map(|name| self.fri_extras.remove(&name))
+            .collect();
+        (self.merkle_extras, fri_extras_list)
+    }
 }
 
-/// Parses the original proof annotations, returning a dictionary from Merkle name to
-/// a list of all its decommitment data from the proof, another dictionary from Merkle name
-/// to the commitments, the trimmed main proof with Merkle decommitments removed,
-/// and its annotations.
-fn parse_fri_merkles_original(
-    orig_proof: Vec<u8>,
-    annot_lines: Vec<String>,
-) -> Result<FriMerklesOriginal, ParseError> {
-    let mut merkle_original_dict = MerkleExtrasDict::new();
-    let mut merkle_commits_dict = HashMap::new();
-    let mut fri_original_dict = HashMap::new();
-    let mut fri_names = Vec::new();
-    let mut eval_points_list = Vec::new();
-    let mut merkle_patches = HashSet::new();
-    let mut main_proof = Vec::new();
-    let mut main_annot = String::new();
-    let mut trace_commitment_counter = 0;
-
-    for line in annot_lines {
-        if is_commitment_line(&line) {
-            let (cline, new_trace_commitment_counter) =
-                parse_commitment_line(&line, &mut trace_commitment_counter)?;
-            merkle_commits_dict.insert(cline.name.clone(), cline);
-            trace_commitment_counter = new_trace_commitment_counter;
-        } else if is_eval_point_line(&line) {
-            let epline = parse_eval_point_line(&line)?;
-            eval_points_list.push(epline);
+impl FriMerklesOriginal {
+    /// Fails if the annotations do not end exactly at the end of the proof of `proof_len` bytes.
+    fn check_len(&self, proof_len: usize) -> Result<(), ProofStructureError> {
+        if self.annotated_len != proof_len {
+            return Err(ProofStructureError::ProofLengthMismatch {
+                proof_len,
+                annotated_len: self.annotated_len,
+            });
         }
+        Ok(())
+    }
 
-        if is_merkle_line(&line) {
-            let mline = parse_merkle_line(&line)?;
-            merkle_original_dict
-                .entry(mline.name.clone())
-                .or_default()
-                .push(mline);
-        } else if is_merkle_data_line(&line) {
-            let mline = parse_merkle_data_line(&line)?;
-            let cloned_mline_name = mline.name.clone();
-            merkle_original_dict
-                .entry(mline.name.clone())
+    /// Adds the annotation at 0-based line `idx` of the original proof `orig_proof`, moving
+    /// the bytes it covers to the trimmed main proof unless it is a Merkle or FRI decommitment.
+    fn push(
+        &mut self,
+        orig_proof: &[u8],
+        idx: usize,
+        annotation: Annotation,
+    ) -> Result<(), AdapterError> {
+        let name = annotation.name().unwrap_or_default().to_string();
+        if let Some(range) = annotation.proof_range() {
+            self.annotated_len = self.annotated_len.max(range.end);
+        }
+        match &annotation {
+            Annotation::Commitment { path, hash, .. } => {
+                let name = commitment_name(path, &mut self.trace_commitment_counter)
+                    .ok_or(ParseError::InvalidLineFormat.at_line("annotations", idx + 1))?;
+                self.merkle_commitments.insert(
+                    name.clone(),
+                    CommitmentLine {
+                        name,
+                        digest: *hash,
+                    },
+                );
+            }
+            Annotation::EvaluationPoint { path, value, .. }
+                if path.iter().any(|segment| segment == "FRI") =>
+            {
+                self.eval_points.push(EvalPointLine {
+                    name: name.clone(),
+                    point: *value,
+                });
+            }
+            Annotation::InteractionElement {
+                direction: Some(Direction::VerifierToProver),
+                value,
+                ..
+            } => self.interaction_elements.push(*value),
+            Annotation::TraceElement {
+                row,
+                column: 0,
+                value,
+                ..
+            } => self
+                .trace_first_columns
+                .entry(name.clone())
                 .or_default()
-                .push(mline);
-            merkle_patches.insert(cloned_mline_name);
-        } else if is_fri_line(&line) {
-            let fline = parse_fri_line(&line)?;
-            if !fri_original_dict.contains_key(&fline.name) {
-                fri_original_dict.insert(fline.name.clone(), Vec::new());
-                fri_names.push(fline.name.clone());
+                .push((*row, *value)),
+            Annotation::QueryIndex { query, .. } => self.queries.push(*query),
+            Annotation::LastLayerCoefficients { values, .. } => {
+                self.last_layer_coefficients = Some(values.len())
             }
-            fri_original_dict.get_mut(&fline.name).unwrap().push(fline);
-        } else {
-            main_annot.push_str(&line);
-            main_annot.push('\n');
-            let (start, end) = line_to_indices(&line)?;
-            main_proof.extend_from_slice(&orig_proof[start..end]);
+            _ => {}
         }
-    }
 
-    Ok(FriMerklesOriginal {
-        merkle_originals: merkle_original_dict,
-        merkle_commitments: merkle_commits_dict,
-        fri_originals: fri_original_dict,
-        eval_points: eval_points_list,
-        fri_names,
-        original_proof: main_proof,
-        merkle_patches,
-    })
+        match annotation {
+            Annotation::DecommitmentNode { node, hash, .. } => {
+                self.merkle_originals
+                    .entry(name.clone())
+                    .or_default()
+                    .push(MerkleLine {
+                        name,
+                        node,
+                        digest: hash,
+                    });
+            }
+            Annotation::DecommitmentData { node, data, .. } => {
+                self.merkle_patches.insert(name.clone());
+                self.merkle_originals
+                    .entry(name.clone())
+                    .or_default()
+                    .push(MerkleLine {
+                        name,
+                        node,
+                        digest: data,
+                    });
+            }
+            Annotation::FriElement {
+                row, column, value, ..
+            } => {
+                if !self.fri_originals.contains_key(&name) {
+                    self.fri_names.push(name.clone());
+                }
+                self.fri_originals
+                    .entry(name.clone())
+                    .or_default()
+                    .push(FriLine {
+                        name,
+                        row,
+                        col: column,
+                        element: value,
+                    });
+            }
+            annotation => {
+                if let Some(range) = annotation.proof_range() {
+                    let segment = orig_proof.get(range.clone()).ok_or(
+                        ProofStructureError::ProofRangeOutOfBounds {
+                            line: idx + 1,
+                            start: range.start,
+                            end: range.end,
+                            proof_len: orig_proof.len(),
+                        },
+                    )?;
+                    self.original_proof.extend_from_slice(segment);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// When any of the traces have a single column, the corresponding Merkle witness is annotated and
@@ -637,45 +513,45 @@ fn parse_fri_merkles_original(
 fn single_column_merkle_patch(
     merkle_patches: &HashSet<String>,
     merkle_extras_dict: &mut HashMap<String, Vec<MerkleLine>>,
-    annot_lines: &[String],
-) -> Result<(), ParseError> {
+    trace_first_columns: &HashMap<String, Vec<(usize, U256)>>,
+) -> Result<(), AdapterError> {
     for name in merkle_patches {
         let merkle_extras = merkle_extras_dict
             .get(name)
-            .ok_or(ParseError::InvalidLineFormat)?
-            .clone();
-        let heights: Vec<usize> = merkle_extras
+            .ok_or_else(|| ProofStructureError::MissingMerkleExtras { name: name.clone() })?;
+        // When patched, the apparent Merkle height is one lower than the original.
+        let height = merkle_height(name, merkle_extras)? + 1;
+
+        let patched = trace_first_columns
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
             .iter()
-            .map(|mline| mline.node.bits() - 1)
+            .map(|(row, value)| MerkleLine {
+                name: name.clone(),
+                node: U256::from(*row) + (U256::one() << height),
+                digest: montgomery_encode(value),
+            })
             .collect();
-        // Ensure all heights are the same
-        let height = *heights.first().ok_or(ParseError::InvalidLineFormat)?;
-        if !heights.iter().all(|&h| h == height) {
-            return Err(ParseError::InvalidLineFormat);
-        }
-        // When patched, the apparent Merkle height is one lower than the original.
-        let height = height + 1;
-        merkle_extras_dict.insert(name.clone(), Vec::new());
-
-        for line in annot_lines {
-            if line.contains(name) && line.contains("Column 0") && line.contains("Field Element") {
-                // It is not a Fri line, but the structure is similar enough for the parser.
-                let parsed_fri_line = parse_fri_line(line)?;
-                let node = U256::from(parsed_fri_line.row) + U256::from(1 << height);
-                let element = montgomery_encode(&parsed_fri_line.element)?;
-                let element_hex = format!("{:0>64x}", element);
-                let merkle_line = MerkleLine {
-                    name: name.clone(),
-                    node,
-                    digest: element_hex,
-                    annotation: line.clone(),
-                };
-                merkle_extras_dict
-                    .get_mut(name)
-                    .ok_or(ParseError::InvalidLineFormat)?
-                    .push(merkle_line);
-            }
-        }
+        merkle_extras_dict.insert(name.clone(), patched);
+    }
+    Ok(())
+}
+
+/// Parses the annotation lines of `section` one at a time, handing each to `push` with its
+/// 0-based line index, so that the parsed annotations are never all held at once.
+fn for_each_annotation<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = Result<S, std::io::Error>>,
+    section: &'static str,
+    mut push: impl FnMut(usize, Annotation) -> Result<(), AdapterError>,
+) -> Result<(), AdapterError> {
+    for (idx, line) in lines.into_iter().enumerate() {
+        let line = line.map_err(|e| ParseError::from(e).at_line(section, idx + 1))?;
+        let annotation = line
+            .as_ref()
+            .parse()
+            .map_err(|e: ParseError| e.at_line(section, idx + 1))?;
+        push(idx, annotation)?;
     }
     Ok(())
 }
@@ -686,75 +562,193 @@ fn single_column_merkle_patch(
 /// in binary form, and a dictionary merkles_statements from the names of the merkle
 /// commitments used in the proof to dictionaries containing all data necessary for
 /// registering them in the Merkle Fact Registry.
+///
+/// Proofs over the extension field are rejected, see [ProofParameters::check_field], and the
+/// public input is checked with [PublicInput::validate] before any statement is produced. Like
+/// [AnnotatedProof::from_readers], fails if the annotations do not end exactly at the end of the
+/// proof.
 pub fn split_fri_merkle_statements(
     annotated_proof: AnnotatedProof,
-) -> Result<SplitProofs, ParseError> {
+) -> Result<SplitProofs, AdapterError> {
+    annotated_proof.proof_parameters.check_field()?;
     // Decode the hexadecimal string
-    let orig_proof = hex::decode(&annotated_proof.proof_hex)?;
-    let (z, alpha) = annotated_proof.extract_interaction_elements();
+    let orig_proof = hex::decode(&annotated_proof.proof_hex).map_err(ParseError::from)?;
+
+    let mut original = FriMerklesOriginal::default();
+    for_each_annotation(
+        annotated_proof.annotations.iter().map(Ok),
+        "annotations",
+        |idx, annotation| original.push(&orig_proof, idx, annotation),
+    )?;
+    original.check_len(orig_proof.len())?;
+    let mut extra = FriMerklesExtra::default();
+    for_each_annotation(
+        annotated_proof.extra_annotations.iter().map(Ok),
+        "extra annotations",
+        |idx, annotation| extra.push(idx, annotation),
+    )?;
 
-    let annot_lines = annotated_proof.annotations;
-    let extra_annot_lines = annotated_proof.extra_annotations;
+    split_collected(
+        original,
+        extra,
+        annotated_proof.proof_parameters,
+        annotated_proof.public_input,
+    )
+}
 
-    let (mut merkle_extras_dict, fri_extras_list) =
-        parse_fri_merkles_extra(extra_annot_lines.iter().map(|s| s.as_str()).collect())?;
-    let fri_merkles_original = parse_fri_merkles_original(orig_proof, annot_lines.clone())?;
+/// Same as [split_fri_merkle_statements], for the three outputs of the stone prover, without
+/// building an [AnnotatedProof]: the proof written by `cpu_air_prover --out_file`, and the
+/// annotations written by `cpu_air_verifier --annotation-file` and `--extra-output-file`.
+///
+/// The annotations are read in a single pass, one line at a time, keeping only the data of the
+/// statements, which is much lighter on memory for proofs with hundreds of thousands of
+/// annotation lines. Like [AnnotatedProof::from_readers], fails if the annotations do not end
+/// exactly at the end of the proof.
+pub fn split_stone_readers(
+    proof_json: impl Read,
+    annotation_txt: impl BufRead,
+    extra_annotation_txt: impl BufRead,
+) -> Result<SplitProofs, AdapterError> {
+    let proof: StoneProof =
+        serde_json::from_reader(BufReader::new(proof_json)).map_err(ParseError::from)?;
+    proof.proof_parameters.check_field()?;
+    let orig_proof = hex::decode(&proof.proof_hex).map_err(ParseError::from)?;
+
+    let mut original = FriMerklesOriginal::default();
+    for_each_annotation(annotation_txt.lines(), "annotations", |idx, annotation| {
+        original.push(&orig_proof, idx, annotation)
+    })?;
+    original.check_len(orig_proof.len())?;
+    let mut extra = FriMerklesExtra::default();
+    for_each_annotation(
+        extra_annotation_txt.lines(),
+        "extra annotations",
+        |idx, annotation| extra.push(idx, annotation),
+    )?;
+
+    split_collected(original, extra, proof.proof_parameters, proof.public_input)
+}
+
+/// Same as [split_stone_readers], reading the files written by the stone prover.
+pub fn split_stone_files(
+    proof_json: impl AsRef<Path>,
+    annotation_txt: impl AsRef<Path>,
+    extra_annotation_txt: impl AsRef<Path>,
+) -> Result<SplitProofs, AdapterError> {
+    let open = |path: &Path| {
+        File::open(path).map_err(|source| AdapterError::File {
+            path: path.display().to_string(),
+            source,
+        })
+    };
+    split_stone_readers(
+        open(proof_json.as_ref())?,
+        BufReader::new(open(annotation_txt.as_ref())?),
+        BufReader::new(open(extra_annotation_txt.as_ref())?),
+    )
+}
+
+/// Builds the statements and the main proof from the collected annotations.
+fn split_collected(
+    fri_merkles_original: FriMerklesOriginal,
+    fri_merkles_extra: FriMerklesExtra,
+    proof_parameters: ProofParameters,
+    public_input: PublicInput,
+) -> Result<SplitProofs, AdapterError> {
+    public_input.validate()?;
+    let (z, alpha) = z_and_alpha(&fri_merkles_original.interaction_elements)?;
+
+    let (mut merkle_extras_dict, fri_extras_list) = fri_merkles_extra.finish();
     let merkle_names: HashSet<_> = HashSet::from_iter(merkle_extras_dict.keys().cloned());
-    assert_eq!(
-        merkle_names,
-        HashSet::from_iter(fri_merkles_original.merkle_originals.keys().cloned())
-    );
+    let original_names: HashSet<_> =
+        HashSet::from_iter(fri_merkles_original.merkle_originals.keys().cloned());
+    if merkle_names != original_names {
+        let sorted = |names: HashSet<&String>| {
+            let mut names: Vec<String> = names.into_iter().cloned().collect();
+            names.sort();
+            names
+        };
+        return Err(ProofStructureError::MerkleNamesMismatch {
+            only_in_annotations: sorted(original_names.difference(&merkle_names).collect()),
+            only_in_extra_annotations: sorted(merkle_names.difference(&original_names).collect()),
+        }
+        .into());
+    }
 
     if !fri_merkles_original.merkle_patches.is_empty() {
         single_column_merkle_patch(
             &fri_merkles_original.merkle_patches,
             &mut merkle_extras_dict,
-            &annot_lines,
+            &fri_merkles_original.trace_first_columns,
         )?;
     }
 
+    check_proof_parameters(
+        &proof_parameters,
+        &fri_merkles_original,
+        &merkle_extras_dict,
+        &fri_extras_list,
+    )?;
+
     let merkle_statements = merkle_names
         .into_iter()
         .filter(|name| !fri_merkles_original.fri_originals.contains_key(name))
         .map(|name| {
             let statement = gen_merkle_statement_call(
-                merkle_extras_dict[&name].clone(),
-                fri_merkles_original.merkle_originals[&name].clone(),
-                fri_merkles_original.merkle_commitments[&name].clone(),
-            )
-            .unwrap();
-            (name, statement)
+                merkle_extras(&merkle_extras_dict, &name)?,
+                merkle_extras(&fri_merkles_original.merkle_originals, &name)?,
+                merkle_commitment(&fri_merkles_original.merkle_commitments, &name)?,
+            )?;
+            Ok((name, statement))
         })
-        .collect::<HashMap<_, _>>();
+        .collect::<Result<HashMap<_, _>, AdapterError>>()?;
 
+    let fri_extras = |layer: usize| {
+        fri_extras_list
+            .get(layer)
+            .cloned()
+            .ok_or(ProofStructureError::MissingFriExtras { layer })
+    };
     let fri_merkle_statements: Vec<FRIMerkleStatement> = fri_merkles_original
         .fri_names
         .into_iter()
         .enumerate()
         .map(|(i, name)| {
             gen_fri_merkle_statement_call(
-                fri_extras_list[i].clone(),
-                fri_extras_list[i + 1].clone(),
-                fri_merkles_original.fri_originals[&name].clone(),
-                fri_merkles_original.merkle_originals[&name].clone(),
-                merkle_extras_dict[&name].clone(),
-                fri_merkles_original.merkle_commitments[&name].clone(),
-                fri_merkles_original.eval_points[i].clone(),
+                fri_extras(i)?,
+                fri_extras(i + 1)?,
+                fri_merkles_original
+                    .fri_originals
+                    .get(&name)
+                    .cloned()
+                    .unwrap_or_default(),
+                merkle_extras(&fri_merkles_original.merkle_originals, &name)?,
+                merkle_extras(&merkle_extras_dict, &name)?,
+                merkle_commitment(&fri_merkles_original.merkle_commitments, &name)?,
+                fri_merkles_original
+                    .eval_points
+                    .get(i)
+                    .cloned()
+                    .ok_or(ProofStructureError::MissingEvaluationPoint { layer: i })?,
             )
         })
-        .collect::<Result<Vec<FRIMerkleStatement>, ParseError>>()?;
+        .collect::<Result<Vec<FRIMerkleStatement>, AdapterError>>()?;
 
     let main_proof = {
         let mut main_proof = fri_merkles_original.original_proof;
 
-        for fri in &fri_merkle_statements[..fri_merkle_statements.len() - 1] {
+        let (_, fri_layers) = fri_merkle_statements
+            .split_last()
+            .ok_or(ProofStructureError::NoFriLayers)?;
+        for fri in fri_layers {
             let fri_output_interleaved = fri
                 .output_interleaved
                 .iter()
                 .map(|val| Token::Uint(*val))
                 .collect();
 
-            let encoded = ethers::abi::encode_packed(&[Token::Array(fri_output_interleaved)])?;
+            let encoded = ethers::abi::encode_packed(&[Token::Array(fri_output_interleaved)])
+                .map_err(ParseError::from)?;
             let hash = keccak256(encoded);
             main_proof.extend_from_slice(&hash);
         }
@@ -763,8 +757,8 @@ pub fn split_fri_merkle_statements(
 
     let main_proof: MainProof = MainProof::new(
         proof_hex2int_list(main_proof),
-        annotated_proof.proof_parameters,
-        annotated_proof.public_input,
+        proof_parameters,
+        public_input,
         z,
         alpha,
     );
@@ -776,6 +770,171 @@ pub fn split_fri_merkle_statements(
     })
 }
 
+/// Checks the decommitments against the declared proof parameters, before any statement is
+/// produced from them.
+///
+/// The parameters give the height of the evaluation domain,
+/// `log2(last_layer_degree_bound) + sum(fri_step_list) + log_n_cosets`, which is the height of
+/// the trace merkle trees. Each FRI layer folds its input by the next step of `fri_step_list`
+/// and commits to the result, and the queries of a layer are those of the evaluation domain
+/// shifted right by the steps so far.
+fn check_proof_parameters(
+    proof_parameters: &ProofParameters,
+    fri_merkles_original: &FriMerklesOriginal,
+    merkle_extras_dict: &MerkleExtrasDict,
+    fri_extras_list: &[FriExtras],
+) -> Result<(), AdapterError> {
+    let fri = &proof_parameters.stark.fri;
+    if !fri.last_layer_degree_bound.is_power_of_two() {
+        return Err(
+            ProofParametersError::InvalidLastLayerDegreeBound(fri.last_layer_degree_bound).into(),
+        );
+    }
+    let steps: Vec<usize> = fri
+        .fri_step_list
+        .iter()
+        .map(|&step| step as usize)
+        .collect();
+    let height = fri.last_layer_degree_bound.trailing_zeros() as usize
+        + steps.iter().sum::<usize>()
+        + proof_parameters.stark.log_n_cosets as usize;
+
+    let queries = &fri_merkles_original.queries;
+    if queries.len() != fri.n_queries as usize {
+        return Err(ProofParametersError::QueryCount {
+            declared: fri.n_queries,
+            found: queries.len(),
+        }
+        .into());
+    }
+    // Heights past 64 bits cannot be an evaluation domain, so they reject every query.
+    let in_range = |query: u64| {
+        u32::try_from(height)
+            .ok()
+            .and_then(|height| query.checked_shr(height))
+            == Some(0)
+    };
+    if let Some(&query) = queries.iter().find(|&&query| !in_range(query)) {
+        return Err(ProofParametersError::QueryOutOfRange { query, height }.into());
+    }
+    let unique_queries = |shift: usize| {
+        queries
+            .iter()
+            .map(|query| query >> shift)
+            .collect::<HashSet<u64>>()
+            .len()
+    };
+
+    let fri_names = &fri_merkles_original.fri_names;
+    if fri_names.len() + 1 != steps.len() {
+        return Err(ProofParametersError::FriLayerCount {
+            declared: steps.len().saturating_sub(1),
+            found: fri_names.len(),
+        }
+        .into());
+    }
+
+    let check_merkle = |name: &str, expected_height: usize, shift: usize| {
+        let extras = merkle_extras(merkle_extras_dict, name)?;
+        let found = merkle_height(name, &extras)?;
+        if found != expected_height {
+            return Err(ProofParametersError::MerkleHeight {
+                name: name.to_string(),
+                expected: expected_height,
+                found,
+            }
+            .into());
+        }
+        let expected = unique_queries(shift);
+        if extras.len() != expected {
+            return Err(ProofParametersError::MerkleQueryCount {
+                name: name.to_string(),
+                expected,
+                found: extras.len(),
+            }
+            .into());
+        }
+        Ok::<(), AdapterError>(())
+    };
+
+    let mut trace_names: Vec<&String> = merkle_extras_dict
+        .keys()
+        .filter(|name| !fri_merkles_original.fri_originals.contains_key(*name))
+        .collect();
+    trace_names.sort();
+    for name in trace_names {
+        check_merkle(name, height, 0)?;
+    }
+
+    for (layer, name) in fri_names.iter().enumerate() {
+        let input_shift: usize = steps[..=layer].iter().sum();
+        let step = steps[layer + 1];
+
+        let fri_extras = fri_extras_list
+            .get(layer)
+            .ok_or(ProofStructureError::MissingFriExtras { layer })?;
+        let found = fri_step_size(
+            name,
+            fri_extras,
+            fri_merkles_original
+                .fri_originals
+                .get(name)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        )?;
+        if found != step {
+            return Err(ProofParametersError::FriStepSize {
+                layer,
+                declared: fri.fri_step_list[layer + 1],
+                found,
+            }
+            .into());
+        }
+        let expected = unique_queries(input_shift);
+        if fri_extras.inverses.len() != expected {
+            return Err(ProofParametersError::FriQueryCount {
+                layer,
+                expected,
+                found: fri_extras.inverses.len(),
+            }
+            .into());
+        }
+        check_merkle(name, height - input_shift - step, input_shift + step)?;
+    }
+
+    if let Some(found) = fri_merkles_original.last_layer_coefficients {
+        if found != fri.last_layer_degree_bound as usize {
+            return Err(ProofParametersError::LastLayerCoefficients {
+                declared: fri.last_layer_degree_bound,
+                found,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+fn merkle_extras(dict: &MerkleExtrasDict, name: &str) -> Result<Vec<MerkleLine>, AdapterError> {
+    Ok(dict
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ProofStructureError::MissingMerkleExtras {
+            name: name.to_string(),
+        })?)
+}
+
+fn merkle_commitment(
+    commitments: &HashMap<String, CommitmentLine>,
+    name: &str,
+) -> Result<CommitmentLine, AdapterError> {
+    Ok(commitments
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ProofStructureError::MissingCommitment {
+            name: name.to_string(),
+        })?)
+}
+
 /// Gets a vec of u8 ints and returns it as a 256bits padded list of integer.
 /// This conversion is what's needed in order to send a binary proof
 /// into an EVM deployed verifier.
@@ -784,7 +943,7 @@ fn proof_hex2int_list(proof: Vec<u8>) -> Vec<U256> {
     let mut padded_proof = proof;
 
     // Pad the vector with zeros until its length is a multiple of chunk_size
-    while padded_proof.len() % chunk_size != 0 {
+    while !padded_proof.len().is_multiple_of(chunk_size) {
         padded_proof.push(0);
     }
 
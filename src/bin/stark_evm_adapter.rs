@@ -1,33 +1,60 @@
-use std::io::BufRead;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    error::Error,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use clap::{Arg, Command};
-// use stark_evm_adapter::{
-//     annotated_proof::AnnotatedProof, annotation_parser::split_fri_merkle_statements,
-// };
+use clap::{Arg, ArgMatches, Command};
+use ethers::{
+    providers::{Http, Provider},
+    types::{Address, U256},
+    utils::parse_units,
+};
+use serde::Serialize;
+use stark_evm_adapter::{
+    annotated_proof::AnnotatedProof,
+    annotation_parser::split_fri_merkle_statements,
+    calldata::{self, SAFE_BATCH_GAS_LIMIT},
+    gas::{self, GasModel, GasSource},
+    network::{NetworkConfig, BUILTIN_NETWORKS},
+    oods_statement::FactTopology,
+    submission::Pipeline,
+    transcript::replay_transcript,
+};
 
 fn main() {
+    let default_batch_gas_limit = SAFE_BATCH_GAS_LIMIT.to_string();
     let matches = Command::new("stark_evm_adapter")
         .version("0.1.0")
         .author("zksecurity <hello@zksecurity.xyz>")
         .about("EVM adapter for the STARK stone-prover")
-        // .subcommand(
-        //     Command::new("split-proof")
-        //         .about("Split an annotated proof into multiple FRI proofs")
-        //         .arg(
-        //             Arg::new("annotated-proof-file")
-        //                 .help("File path for annotated proof json file")
-        //                 .long("annotated-proof-file")
-        //                 .takes_value(true)
-        //                 .required(true),
-        //         )
-        //         .arg(
-        //             Arg::new("output")
-        //                 .help("File path for generated split proofs json file")
-        //                 .long("output")
-        //                 .takes_value(true)
-        //                 .required(true),
-        //         ),
-        // )
+        .subcommand(
+            Command::new("split-proof")
+                .about("Split an annotated proof into a main proof, merkle statements and FRI statements")
+                .arg(
+                    Arg::new("annotated-proof-file")
+                        .help("File path for annotated proof json file")
+                        .long("annotated-proof-file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("File path for generated split proofs json file")
+                        .long("output")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("statements-dir")
+                        .help("Directory to additionally write one json file per statement (trace_0.json, fri_0.json, main.json, ...)")
+                        .long("statements-dir")
+                        .takes_value(true)
+                        .required(false),
+                ),
+        )
         .subcommand(
             Command::new("gen-annotated-proof")
                 .about("Merge stone proof and annotations into a single annotated proof json file")
@@ -60,63 +87,550 @@ fn main() {
                         .required(true),
                 ),
         )
+        .subcommand(
+            Command::new("fact-topologies")
+                .about("Derive the fact topologies of the bootloader tasks from the public memory of an annotated proof")
+                .arg(
+                    Arg::new("annotated-proof-file")
+                        .help("File path for annotated proof json file")
+                        .long("annotated-proof-file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("File path for generated fact topologies json file")
+                        .long("output")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("simple-bootloader")
+                        .help("The proven program is the simple bootloader, whose output does not start with the bootloader config")
+                        .long("simple-bootloader")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::new("tree-structures-file")
+                        .help("File path for a json object from task index to the gps_fact_topology tree structure of its program, needed for tasks whose output spans several pages")
+                        .long("tree-structures-file")
+                        .takes_value(true)
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            Command::new("estimate-gas")
+                .about("Estimate the gas and ETH cost of submitting the split proofs of an annotated proof")
+                .arg(
+                    Arg::new("annotated-proof-file")
+                        .help("File path for annotated proof json file")
+                        .long("annotated-proof-file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("fact-topologies-file")
+                        .help("File path for the fact topologies json file written by the bootloader runner, derived from the proof if not given")
+                        .long("fact-topologies-file")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("simple-bootloader")
+                        .help("The proven program is the simple bootloader, whose output does not start with the bootloader config")
+                        .long("simple-bootloader")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::new("gas-price")
+                        .help("Gas price in gwei used to compute the cost")
+                        .long("gas-price")
+                        .takes_value(true)
+                        .default_value("30"),
+                )
+                .arg(
+                    Arg::new("rpc-url")
+                        .help("RPC url of a node or local Anvil to estimate with eth_estimateGas, otherwise an offline model is used")
+                        .long("rpc-url")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("from")
+                        .help("Address the transactions are estimated from when using --rpc-url")
+                        .long("from")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("network")
+                        .help("Built-in network profile of the verifier contracts. Only estimate-gas and export-calldata address a network, the other commands run offline")
+                        .long("network")
+                        .takes_value(true)
+                        .possible_values(BUILTIN_NETWORKS)
+                        .default_value("mainnet"),
+                )
+                .arg(
+                    Arg::new("network-config")
+                        .help("File path for a TOML or JSON network config of the verifier contracts, overrides --network")
+                        .long("network-config")
+                        .takes_value(true)
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            Command::new("export-calldata")
+                .about("Write the ABI-encoded calldata of every transaction of the submission, for signing outside of this tool")
+                .arg(
+                    Arg::new("annotated-proof-file")
+                        .help("File path for annotated proof json file")
+                        .long("annotated-proof-file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("File path for the exported calldata json file")
+                        .long("output")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .help("json for a plain list of calls, safe for a Safe Transaction Builder batch")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(["json", "safe"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::new("chain-id")
+                        .help("Chain id of the Safe Transaction Builder batch, the one of the network if not given")
+                        .long("chain-id")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("safe-address")
+                        .help("Address of the Safe executing the Safe Transaction Builder batch")
+                        .long("safe-address")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("batch-gas-limit")
+                        .help("Estimated gas above which the Safe Transaction Builder batch is split into several files, to be executed in order")
+                        .long("batch-gas-limit")
+                        .takes_value(true)
+                        .default_value(&default_batch_gas_limit),
+                )
+                .arg(
+                    Arg::new("fact-topologies-file")
+                        .help("File path for the fact topologies json file written by the bootloader runner, derived from the proof if not given")
+                        .long("fact-topologies-file")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("simple-bootloader")
+                        .help("The proven program is the simple bootloader, whose output does not start with the bootloader config")
+                        .long("simple-bootloader")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::new("network")
+                        .help("Built-in network profile of the verifier contracts. Only estimate-gas and export-calldata address a network, the other commands run offline")
+                        .long("network")
+                        .takes_value(true)
+                        .possible_values(BUILTIN_NETWORKS)
+                        .default_value("mainnet"),
+                )
+                .arg(
+                    Arg::new("network-config")
+                        .help("File path for a TOML or JSON network config of the verifier contracts, overrides --network")
+                        .long("network-config")
+                        .takes_value(true)
+                        .required(false),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
-        // Some(("split-proof", sub_matches)) => {
-        //     let annotated_proof_filepath = sub_matches.value_of("annotated-proof-file").unwrap();
-        //     let output_filepath = sub_matches.value_of("output").unwrap();
-
-        //     // load annotated proof from file
-        //     let reader = std::fs::File::open(annotated_proof_filepath).unwrap();
-
-        //     // parse as annotated proof
-        //     let annotated_proof: AnnotatedProof = serde_json::from_reader(reader).unwrap();
-
-        //     // create the split proofs
-        //     let split_proofs = split_fri_merkle_statements(annotated_proof).unwrap();
+        Some(("split-proof", sub_matches)) => {
+            let annotated_proof_filepath = sub_matches.value_of("annotated-proof-file").unwrap();
+            let output_filepath = sub_matches.value_of("output").unwrap();
+            let statements_dir = sub_matches.value_of("statements-dir");
 
-        //     // format json and write to file
-        //     let split_proof_json = serde_json::to_string_pretty(&split_proofs).unwrap();
-        //     std::fs::write(output_filepath, split_proof_json).unwrap();
+            if let Err(err) = split_proof(annotated_proof_filepath, output_filepath, statements_dir)
+            {
+                eprintln!("split-proof failed: {}", err);
+                std::process::exit(1);
+            }
 
-        //     println!("split proof wrote to {}", output_filepath);
-        // }
+            println!("split proof wrote to {}", output_filepath);
+        }
         Some(("gen-annotated-proof", sub_matches)) => {
             let proof_filepath = sub_matches.value_of("proof-file").unwrap();
             let annotation_filepath = sub_matches.value_of("annotation-file").unwrap();
             let extra_annotation_filepath = sub_matches.value_of("extra-annotation-file").unwrap();
             let output_filepath = sub_matches.value_of("output").unwrap();
 
-            // load proof file as json
-            let proof_reader = std::fs::File::open(proof_filepath).unwrap();
-            let proof: serde_json::Value = serde_json::from_reader(proof_reader).unwrap();
-
-            // load annotation file and save the lines as an array property (annotations) in the proof json file
-            let annotation_reader = std::fs::File::open(annotation_filepath).unwrap();
-            let annotation_lines: Vec<String> = std::io::BufReader::new(annotation_reader)
-                .lines()
-                .map(|line| line.unwrap())
-                .collect();
-            let mut proof_with_annotations = proof.clone();
-            proof_with_annotations["annotations"] = serde_json::json!(annotation_lines);
-
-            // load extra annotation file and save the lines as an array property (extra_annotations) in the proof json file
-            let extra_annotation_reader = std::fs::File::open(extra_annotation_filepath).unwrap();
-            let extra_annotation_lines: Vec<String> =
-                std::io::BufReader::new(extra_annotation_reader)
-                    .lines()
-                    .map(|line| line.unwrap())
-                    .collect();
-            proof_with_annotations["extra_annotations"] = serde_json::json!(extra_annotation_lines);
-
-            // format json and write to file
-            let proof_with_annotations_json =
-                serde_json::to_string_pretty(&proof_with_annotations).unwrap();
-            std::fs::write(output_filepath, proof_with_annotations_json).unwrap();
+            if let Err(err) = gen_annotated_proof(
+                proof_filepath,
+                annotation_filepath,
+                extra_annotation_filepath,
+                output_filepath,
+            ) {
+                eprintln!("gen-annotated-proof failed: {}", err);
+                std::process::exit(1);
+            }
 
             println!("annotated proof wrote to {}", output_filepath);
         }
+        Some(("fact-topologies", sub_matches)) => {
+            let annotated_proof_filepath = sub_matches.value_of("annotated-proof-file").unwrap();
+            let output_filepath = sub_matches.value_of("output").unwrap();
+            let include_bootloader_config = !sub_matches.is_present("simple-bootloader");
+            let tree_structures_filepath = sub_matches.value_of("tree-structures-file");
+
+            if let Err(err) = fact_topologies(
+                annotated_proof_filepath,
+                output_filepath,
+                include_bootloader_config,
+                tree_structures_filepath,
+            ) {
+                eprintln!("fact-topologies failed: {}", err);
+                std::process::exit(1);
+            }
+
+            println!("fact topologies wrote to {}", output_filepath);
+        }
+        Some(("estimate-gas", sub_matches)) => {
+            let submission = SubmissionArgs::from_matches(sub_matches);
+            let gas_price = sub_matches.value_of("gas-price").unwrap();
+            let rpc_url = sub_matches.value_of("rpc-url");
+            let from = sub_matches.value_of("from");
+
+            if let Err(err) = estimate_gas(&submission, gas_price, rpc_url, from) {
+                eprintln!("estimate-gas failed: {}", err);
+                std::process::exit(1);
+            }
+        }
+        Some(("export-calldata", sub_matches)) => {
+            let submission = SubmissionArgs::from_matches(sub_matches);
+            let output_filepath = sub_matches.value_of("output").unwrap();
+            let format = sub_matches.value_of("format").unwrap();
+            let chain_id = sub_matches.value_of("chain-id");
+            let safe_address = sub_matches.value_of("safe-address");
+            let batch_gas_limit = sub_matches.value_of("batch-gas-limit").unwrap();
+
+            match export_calldata(
+                &submission,
+                output_filepath,
+                format,
+                chain_id,
+                safe_address,
+                batch_gas_limit,
+            ) {
+                Ok(paths) => {
+                    for path in paths {
+                        println!("calldata wrote to {}", path.display());
+                    }
+                }
+                Err(err) => {
+                    eprintln!("export-calldata failed: {}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
         _ => unreachable!("Unhandled subcommand"),
     }
 }
+
+/// Merges the stone prover outputs into an [AnnotatedProof] and writes it to `output_filepath`.
+fn gen_annotated_proof(
+    proof_filepath: &str,
+    annotation_filepath: &str,
+    extra_annotation_filepath: &str,
+    output_filepath: &str,
+) -> Result<(), Box<dyn Error>> {
+    let annotated_proof = AnnotatedProof::from_stone_files(
+        proof_filepath,
+        annotation_filepath,
+        extra_annotation_filepath,
+    )?;
+    write_json(Path::new(output_filepath), &annotated_proof)
+}
+
+/// Derives the fact topologies of the tasks proven in the annotated proof at
+/// `annotated_proof_filepath` and writes them to `output_filepath`, in the format of the
+/// `fact_topologies.json` written by the cairo-lang bootloader runner. The tree structures of
+/// tasks whose output spans several pages are read from `tree_structures_filepath`, a json
+/// object such as `{ "0": [2, 1, 0, 2] }`.
+fn fact_topologies(
+    annotated_proof_filepath: &str,
+    output_filepath: &str,
+    include_bootloader_config: bool,
+    tree_structures_filepath: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let reader = std::fs::File::open(annotated_proof_filepath)
+        .map_err(|e| format!("cannot open {}: {}", annotated_proof_filepath, e))?;
+    let annotated_proof: AnnotatedProof = serde_json::from_reader(std::io::BufReader::new(reader))
+        .map_err(|e| {
+            format!(
+                "invalid annotated proof {}: {}",
+                annotated_proof_filepath, e
+            )
+        })?;
+
+    let tree_structures: HashMap<usize, Vec<u8>> = match tree_structures_filepath {
+        Some(path) => {
+            let file = std::fs::read_to_string(path)
+                .map_err(|e| format!("cannot open {}: {}", path, e))?;
+            serde_json::from_str(&file)
+                .map_err(|e| format!("invalid tree structures {}: {}", path, e))?
+        }
+        None => HashMap::new(),
+    };
+    let fact_topologies = annotated_proof
+        .public_input
+        .fact_topologies(include_bootloader_config, &tree_structures)?;
+
+    write_json(
+        Path::new(output_filepath),
+        &serde_json::json!({ "fact_topologies": fact_topologies }),
+    )
+}
+
+/// Arguments of the subcommands that build the submission of an annotated proof.
+struct SubmissionArgs<'a> {
+    annotated_proof_filepath: &'a str,
+    fact_topologies_filepath: Option<&'a str>,
+    include_bootloader_config: bool,
+    network: &'a str,
+    network_config: Option<&'a str>,
+}
+
+impl<'a> SubmissionArgs<'a> {
+    fn from_matches(matches: &'a ArgMatches) -> SubmissionArgs<'a> {
+        SubmissionArgs {
+            annotated_proof_filepath: matches.value_of("annotated-proof-file").unwrap(),
+            fact_topologies_filepath: matches.value_of("fact-topologies-file"),
+            include_bootloader_config: !matches.is_present("simple-bootloader"),
+            network: matches.value_of("network").unwrap(),
+            network_config: matches.value_of("network-config"),
+        }
+    }
+
+    /// Loads the network config file if given, the built-in network otherwise.
+    fn network(&self) -> Result<NetworkConfig, Box<dyn Error>> {
+        Ok(match self.network_config {
+            Some(path) => NetworkConfig::load(path)?,
+            None => self.network.parse()?,
+        })
+    }
+
+    /// Splits the annotated proof into the [Pipeline] submitting it to the verifier contracts of
+    /// `network`. The fact topologies are read from the fact topologies file if given, derived
+    /// from the public memory otherwise.
+    fn pipeline(&self, network: &NetworkConfig) -> Result<Pipeline, Box<dyn Error>> {
+        let annotated_proof_filepath = self.annotated_proof_filepath;
+        let reader = std::fs::File::open(annotated_proof_filepath)
+            .map_err(|e| format!("cannot open {}: {}", annotated_proof_filepath, e))?;
+        let annotated_proof: AnnotatedProof =
+            serde_json::from_reader(std::io::BufReader::new(reader)).map_err(|e| {
+                format!(
+                    "invalid annotated proof {}: {}",
+                    annotated_proof_filepath, e
+                )
+            })?;
+        let split_proofs = split_fri_merkle_statements(annotated_proof)?;
+
+        let fact_topologies: Vec<FactTopology> = match self.fact_topologies_filepath {
+            Some(path) => {
+                let file = std::fs::read_to_string(path)
+                    .map_err(|e| format!("cannot open {}: {}", path, e))?;
+                let topology_json: serde_json::Value = serde_json::from_str(&file)?;
+                serde_json::from_value(
+                    topology_json
+                        .get("fact_topologies")
+                        .ok_or_else(|| format!("{} has no fact_topologies", path))?
+                        .clone(),
+                )?
+            }
+            None => split_proofs
+                .main_proof
+                .public_input
+                .fact_topologies(self.include_bootloader_config, &HashMap::new())?,
+        };
+
+        // The state file is only used when the pipeline is run
+        let mut pipeline = Pipeline::new(
+            split_proofs,
+            fact_topologies,
+            network.contracts.clone(),
+            "submission_state.json",
+        )?;
+        pipeline.include_bootloader_config = self.include_bootloader_config;
+        pipeline.chain_id = Some(network.chain_id);
+        Ok(pipeline)
+    }
+}
+
+/// Prints the estimated gas of every transaction of the submission, with `eth_estimateGas` on
+/// `rpc_url` if given, offline otherwise.
+fn estimate_gas(
+    submission: &SubmissionArgs,
+    gas_price: &str,
+    rpc_url: Option<&str>,
+    from: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let gas_price = parse_units(gas_price, "gwei")
+        .map_err(|e| format!("invalid gas price {}: {}", gas_price, e))?
+        .into();
+    let pipeline = submission.pipeline(&submission.network()?)?;
+
+    let model = GasModel::default();
+    let report = match rpc_url {
+        Some(rpc_url) => {
+            let provider = Provider::<Http>::try_from(rpc_url)?;
+            let from = from.map(Address::from_str).transpose()?;
+            tokio::runtime::Runtime::new()?
+                .block_on(gas::estimate(&pipeline, &provider, from, &model))?
+        }
+        None => gas::estimate_offline(&pipeline, &model)?,
+    };
+
+    print!("{}", report.table(gas_price));
+    for call in &report.calls {
+        if let GasSource::ModelFallback(message) = &call.source {
+            eprintln!("{}: eth_estimateGas reverted: {}", call.step, message);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the calldata of every transaction of the submission to `output_filepath`, as a plain
+/// list or as Safe batches. Returns the paths written: when the submission does not fit in a
+/// single batch of `batch_gas_limit` gas, the batches are written to `<output>_1.json`,
+/// `<output>_2.json`, ... in the order they must be executed.
+fn export_calldata(
+    submission: &SubmissionArgs,
+    output_filepath: &str,
+    format: &str,
+    chain_id: Option<&str>,
+    safe_address: Option<&str>,
+    batch_gas_limit: &str,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let network = submission.network()?;
+    let pipeline = submission.pipeline(&network)?;
+    let output = Path::new(output_filepath);
+
+    match format {
+        "safe" => {
+            let chain_id: u64 = match chain_id {
+                Some(chain_id) => chain_id
+                    .parse()
+                    .map_err(|e| format!("invalid chain id {}: {}", chain_id, e))?,
+                None => network.chain_id,
+            };
+            let safe_address = safe_address.map(Address::from_str).transpose()?;
+            let batch_gas_limit = U256::from_dec_str(batch_gas_limit)
+                .map_err(|e| format!("invalid batch gas limit {}: {}", batch_gas_limit, e))?;
+            let batches = calldata::safe_batches(
+                &pipeline,
+                &GasModel::default(),
+                batch_gas_limit,
+                chain_id,
+                safe_address,
+            )?;
+
+            let paths: Vec<PathBuf> = match batches.len() {
+                1 => vec![output.to_path_buf()],
+                n_batches => (1..=n_batches)
+                    .map(|index| {
+                        let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+                        output.with_file_name(format!("{}_{}.json", stem, index))
+                    })
+                    .collect(),
+            };
+            for (path, batch) in paths.iter().zip(&batches) {
+                write_json(path, batch)?;
+            }
+            Ok(paths)
+        }
+        _ => {
+            write_json(output, &calldata::export_calls(&pipeline)?)?;
+            Ok(vec![output.to_path_buf()])
+        }
+    }
+}
+
+/// Splits the annotated proof at `annotated_proof_filepath` and writes the [SplitProofs] json to
+/// `output_filepath`. When `statements_dir` is given, each statement is also written to its own file.
+///
+/// [SplitProofs]: stark_evm_adapter::annotation_parser::SplitProofs
+fn split_proof(
+    annotated_proof_filepath: &str,
+    output_filepath: &str,
+    statements_dir: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    // load annotated proof from file
+    let reader = std::fs::File::open(annotated_proof_filepath)
+        .map_err(|e| format!("cannot open {}: {}", annotated_proof_filepath, e))?;
+
+    // parse as annotated proof
+    let annotated_proof: AnnotatedProof = serde_json::from_reader(std::io::BufReader::new(reader))
+        .map_err(|e| {
+            format!(
+                "invalid annotated proof {}: {}",
+                annotated_proof_filepath, e
+            )
+        })?;
+
+    // reject annotations that do not belong to this proof
+    let mismatches = replay_transcript(&annotated_proof)?;
+    if !mismatches.is_empty() {
+        for mismatch in &mismatches {
+            eprintln!("{}", mismatch);
+        }
+        return Err(format!(
+            "{} annotated values do not match the proof transcript",
+            mismatches.len()
+        )
+        .into());
+    }
+
+    // create the split proofs
+    let split_proofs = split_fri_merkle_statements(annotated_proof)?;
+
+    // reject a bad split before it is written out
+    split_proofs.verify_locally()?;
+
+    if let Some(dir) = statements_dir {
+        let dir = Path::new(dir);
+        std::fs::create_dir_all(dir)?;
+
+        for (name, statement) in &split_proofs.merkle_statements {
+            write_json(&dir.join(statement_file_name(name)), statement)?;
+        }
+        for (i, statement) in split_proofs.fri_merkle_statements.iter().enumerate() {
+            write_json(&dir.join(format!("fri_{}.json", i)), statement)?;
+        }
+        write_json(&dir.join("main.json"), &split_proofs.main_proof)?;
+    }
+
+    // format json and write to file
+    write_json(Path::new(output_filepath), &split_proofs)
+}
+
+/// Maps a merkle statement name such as `Trace 0` to its file name, `trace_0.json`.
+fn statement_file_name(name: &str) -> String {
+    format!("{}.json", name.to_lowercase().replace(' ', "_"))
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    let json = serde_json::to_string_pretty(value)?;
+    std::fs::write(path, json).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    Ok(())
+}
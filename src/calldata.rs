@@ -0,0 +1,186 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ethers::{
+    contract::EthCall,
+    types::{Address, Bytes, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::AdapterError,
+    fri_merkle_statement::VerifyFRICall,
+    gas::GasModel,
+    merkle_statement::VerifyMerkleCall,
+    oods_statement::{RegisterContinuousMemoryPageCall, VerifyProofAndRegisterCall},
+    submission::{Pipeline, Step},
+};
+
+/// Default gas budget of a [SafeBatch] in [safe_batches], well below the block gas limit of
+/// mainnet and Sepolia so that the batch fits in a block next to other transactions.
+pub const SAFE_BATCH_GAS_LIMIT: u64 = 15_000_000;
+
+/// A transaction of the submission as raw calldata, to be signed outside of this crate, for
+/// example by a multisig or a hardware signer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExportedCall {
+    pub step: String,
+    pub to: Address,
+    /// Signature of the called function, such as `verifyMerkle(uint256[],uint256[],uint256,uint256)`.
+    pub function: String,
+    /// The first 4 bytes of `data`.
+    pub selector: Bytes,
+    /// ABI-encoded calldata, starting with the selector.
+    pub data: Bytes,
+}
+
+/// Encodes every step of `pipeline`, in the order they must be sent.
+pub fn export_calls(pipeline: &Pipeline) -> Result<Vec<ExportedCall>, AdapterError> {
+    pipeline
+        .steps()
+        .into_iter()
+        .map(|step| {
+            let (function, selector) = match step {
+                Step::MerkleStatement(_) => (
+                    VerifyMerkleCall::abi_signature(),
+                    VerifyMerkleCall::selector(),
+                ),
+                Step::FriStatement(_) => {
+                    (VerifyFRICall::abi_signature(), VerifyFRICall::selector())
+                }
+                Step::ContinuousMemoryPage(_) => (
+                    RegisterContinuousMemoryPageCall::abi_signature(),
+                    RegisterContinuousMemoryPageCall::selector(),
+                ),
+                Step::MainProof => (
+                    VerifyProofAndRegisterCall::abi_signature(),
+                    VerifyProofAndRegisterCall::selector(),
+                ),
+            };
+            Ok(ExportedCall {
+                step: step.name(),
+                to: pipeline.target(&step),
+                function: function.into_owned(),
+                selector: selector.to_vec().into(),
+                data: pipeline.calldata(&step)?,
+            })
+        })
+        .collect()
+}
+
+/// A batch file of the Safe Transaction Builder app.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeBatch {
+    pub version: String,
+    pub chain_id: String,
+    /// Creation time in milliseconds since the unix epoch.
+    pub created_at: u64,
+    pub meta: SafeBatchMeta,
+    pub transactions: Vec<SafeTransaction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeBatchMeta {
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_from_safe_address: Option<Address>,
+}
+
+/// A transaction of a [SafeBatch], given as raw calldata rather than as a contract method and
+/// its input values.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTransaction {
+    pub to: Address,
+    pub value: String,
+    pub data: Bytes,
+    pub contract_method: Option<serde_json::Value>,
+    pub contract_inputs_values: Option<serde_json::Value>,
+}
+
+/// Wraps `calls` into a single Safe Transaction Builder batch for `chain_id`, optionally bound to
+/// the Safe at `safe_address`. See [safe_batches] to keep each batch under a gas budget.
+pub fn safe_batch(
+    calls: &[ExportedCall],
+    chain_id: u64,
+    safe_address: Option<Address>,
+) -> SafeBatch {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64);
+
+    SafeBatch {
+        version: "1.0".to_string(),
+        chain_id: chain_id.to_string(),
+        created_at,
+        meta: SafeBatchMeta {
+            name: "STARK proof submission".to_string(),
+            description: calls
+                .iter()
+                .map(|call| call.step.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "),
+            created_from_safe_address: safe_address,
+        },
+        transactions: calls
+            .iter()
+            .map(|call| SafeTransaction {
+                to: call.to,
+                value: "0".to_string(),
+                data: call.data.clone(),
+                contract_method: None,
+                contract_inputs_values: None,
+            })
+            .collect(),
+    }
+}
+
+/// Splits the submission of `pipeline` into Safe Transaction Builder batches whose gas, as
+/// estimated by `model`, stays under `gas_limit`, since a single batch of every step exceeds the
+/// block gas limit.
+///
+/// The steps keep the order of [export_calls], so the batches must be executed one after the
+/// other: the statements and memory pages come first, and the main proof, which needs their
+/// facts, is always in the last batch. A step estimated above `gas_limit` on its own gets a batch
+/// of its own. Each call is counted with the base cost of a transaction, which overestimates the
+/// gas of a batch sent as a single `multiSend` transaction.
+pub fn safe_batches(
+    pipeline: &Pipeline,
+    model: &GasModel,
+    gas_limit: U256,
+    chain_id: u64,
+    safe_address: Option<Address>,
+) -> Result<Vec<SafeBatch>, AdapterError> {
+    let calls = export_calls(pipeline)?;
+
+    let mut chunks: Vec<Vec<ExportedCall>> = vec![];
+    let mut chunk_gas = U256::zero();
+    for (call, step) in calls.into_iter().zip(pipeline.steps()) {
+        let gas = model.gas(&step, &call.data);
+        match chunks.last_mut() {
+            Some(chunk) if chunk_gas + gas <= gas_limit => {
+                chunk.push(call);
+                chunk_gas += gas;
+            }
+            _ => {
+                chunks.push(vec![call]);
+                chunk_gas = gas;
+            }
+        }
+    }
+
+    let n_batches = chunks.len();
+    Ok(chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut batch = safe_batch(chunk, chain_id, safe_address);
+            if n_batches > 1 {
+                batch.meta.name = format!("{} ({}/{})", batch.meta.name, index + 1, n_batches);
+            }
+            batch
+        })
+        .collect())
+}
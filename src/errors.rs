@@ -3,69 +3,464 @@ use ethers::{
         ethereum_types::{FromDecStrErr, FromStrRadixErr},
         EncodePackedError,
     },
+    types::U256,
     utils::hex::FromHexError,
 };
-use num_bigint::ParseBigIntError;
 use std::num::ParseIntError;
 use thiserror::Error;
 
-// Define the custom error type using `thiserror`.
+use crate::layout::Layout;
+
+/// Top-level error of the crate, grouping the errors of every stage from parsing an annotated
+/// proof to submitting it on-chain.
+#[derive(Error, Debug)]
+pub enum AdapterError {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error(transparent)]
+    ProofStructure(#[from] ProofStructureError),
+    #[error(transparent)]
+    ProofParameters(#[from] ProofParametersError),
+    #[error(transparent)]
+    PublicInput(#[from] PublicInputError),
+    #[error(transparent)]
+    Topology(#[from] TopologyError),
+    #[error(transparent)]
+    Verification(#[from] StatementVerificationError),
+    #[error(transparent)]
+    Submission(#[from] SubmissionError),
+    #[error(transparent)]
+    Deployment(#[from] DeploymentError),
+    #[error("cannot open {path}: {source}")]
+    File {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error(
+        "unknown network {name}, expected one of {:?} or a config file",
+        crate::network::BUILTIN_NETWORKS
+    )]
+    UnknownNetwork { name: String },
+}
+
+/// Errors raised while reading the inputs of the adapter: the text of an annotation line, and
+/// the whole JSON or TOML files and annotation streams it is read from.
 #[derive(Error, Debug)]
 pub enum ParseError {
-    #[error("regex error")]
-    RegexError,
-    #[error("parse u256 error")]
-    ParseU256Error,
-    #[error("parse integer error")]
-    ParseIntError,
-    #[error("parse bigint error")]
-    ParseBigUIntError,
-    #[error("hex extraction error")]
+    #[error("invalid u256: {0}")]
+    ParseU256Error(String),
+    #[error("invalid integer: {0}")]
+    ParseIntError(#[from] ParseIntError),
+    #[error("no hex value found in line")]
     HexExtractionError,
+    #[error("invalid hex: {0}")]
+    InvalidHex(#[from] FromHexError),
     #[error("invalid line format")]
     InvalidLineFormat,
-    #[error("ether encoding error")]
-    EtherEncodingError,
+    #[error("unrecognized annotation: {0:?}")]
+    UnknownAnnotation(String),
+    #[error("invalid json: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("invalid toml: {0}")]
+    InvalidToml(#[from] toml::de::Error),
+    #[error("cannot read annotations: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("ether encoding error: {0}")]
+    EtherEncodingError(#[from] EncodePackedError),
+    #[error("{section} line {line}: {source}")]
+    AtLine {
+        section: &'static str,
+        line: usize,
+        source: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Attaches the 1-based line number of the offending annotation line.
+    pub(crate) fn at_line(self, section: &'static str, line: usize) -> ParseError {
+        ParseError::AtLine {
+            section,
+            line,
+            source: Box::new(self),
+        }
+    }
 }
 
 impl From<FromDecStrErr> for ParseError {
-    fn from(_: FromDecStrErr) -> Self {
-        ParseError::ParseU256Error
+    fn from(e: FromDecStrErr) -> Self {
+        ParseError::ParseU256Error(e.to_string())
     }
 }
 
 impl From<FromStrRadixErr> for ParseError {
-    fn from(_: FromStrRadixErr) -> Self {
-        ParseError::ParseU256Error
+    fn from(e: FromStrRadixErr) -> Self {
+        ParseError::ParseU256Error(e.to_string())
     }
 }
 
-impl From<ParseBigIntError> for ParseError {
-    fn from(_: ParseBigIntError) -> Self {
-        ParseError::ParseBigUIntError
-    }
+/// Errors raised when the annotations parse, but do not describe a proof that can be split.
+#[derive(Error, Debug)]
+pub enum ProofStructureError {
+    #[error("expected 3 or 6 interaction elements, found {found}")]
+    InteractionElementCount { found: usize },
+    #[error("annotation line {line} references proof bytes [{start}:{end}] but the proof has {proof_len} bytes")]
+    ProofRangeOutOfBounds {
+        line: usize,
+        start: usize,
+        end: usize,
+        proof_len: usize,
+    },
+    #[error("proof has {proof_len} bytes but the annotations cover {annotated_len} bytes")]
+    ProofLengthMismatch {
+        proof_len: usize,
+        annotated_len: usize,
+    },
+    #[error(
+        "extra annotations reference merkle trees not decommitted in the annotations: {names:?}"
+    )]
+    UnknownExtraMerkles { names: Vec<String> },
+    #[error("merkle decommitments differ between annotations and extra annotations: only in annotations {only_in_annotations:?}, only in extra annotations {only_in_extra_annotations:?}")]
+    MerkleNamesMismatch {
+        only_in_annotations: Vec<String>,
+        only_in_extra_annotations: Vec<String>,
+    },
+    #[error("no commitment found for merkle {name}")]
+    MissingCommitment { name: String },
+    #[error("no extra annotations found for merkle {name}")]
+    MissingMerkleExtras { name: String },
+    #[error("merkle {name} has an invalid node {node}")]
+    InvalidMerkleNode { name: String, node: U256 },
+    #[error("merkle {name} has decommitment nodes of different heights")]
+    InconsistentMerkleHeights { name: String },
+    #[error("fri layer {name} has rows of different widths")]
+    InconsistentFriRowWidths { name: String },
+    #[error("fri layer {name} has rows of {width} elements, which is not a power of two")]
+    InvalidFriRowWidth { name: String, width: usize },
+    #[error(
+        "extra annotation line {line} has an xInv for fri layer {name} before any of its values"
+    )]
+    FriInverseWithoutValues { name: String, line: usize },
+    #[error("no extra annotations found for fri layer {layer}")]
+    MissingFriExtras { layer: usize },
+    #[error("no evaluation point found for fri layer {layer}")]
+    MissingEvaluationPoint { layer: usize },
+    #[error("the proof has no fri layers")]
+    NoFriLayers,
 }
 
-impl From<regex::Error> for ParseError {
-    fn from(_: regex::Error) -> Self {
-        ParseError::RegexError
-    }
+/// Errors raised when the decommitments in the annotations do not match the declared
+/// [ProofParameters](crate::annotated_proof::ProofParameters).
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProofParametersError {
+    #[error("the proof uses the extension field, for which no EVM verifier exists")]
+    ExtensionField,
+    #[error("last_layer_degree_bound {0} is not a power of two")]
+    InvalidLastLayerDegreeBound(u32),
+    #[error("the annotations have {found} query indices, but n_queries is {declared}")]
+    QueryCount { declared: u32, found: usize },
+    #[error("query {query} is outside of the evaluation domain of 2^{height} points")]
+    QueryOutOfRange { query: u64, height: usize },
+    #[error("the evaluation domain of 2^{log_size} points does not fit the query indices")]
+    EvaluationDomainTooLarge { log_size: u32 },
+    #[error("the annotations have {found} fri layers, but fri_step_list declares {declared}")]
+    FriLayerCount { declared: usize, found: usize },
+    #[error("fri layer {layer} has step size {found}, but fri_step_list declares {declared}")]
+    FriStepSize {
+        layer: usize,
+        declared: u32,
+        found: usize,
+    },
+    #[error(
+        "merkle tree {name} has height {found}, expected {expected} from the proof parameters"
+    )]
+    MerkleHeight {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("merkle tree {name} decommits {found} nodes, expected {expected} for the queries")]
+    MerkleQueryCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("fri layer {layer} has {found} queries, expected {expected}")]
+    FriQueryCount {
+        layer: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error(
+        "the last fri layer has {found} coefficients, but last_layer_degree_bound is {declared}"
+    )]
+    LastLayerCoefficients { declared: u32, found: usize },
 }
 
-impl From<ParseIntError> for ParseError {
-    fn from(_: ParseIntError) -> Self {
-        ParseError::RegexError
-    }
+/// Errors raised when the cairo public input cannot be serialized for the verifier.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PublicInputError {
+    #[error("public memory is empty")]
+    EmptyPublicMemory,
+    #[error("duplicate public memory entries found with the same address: {address}")]
+    DuplicateAddress { address: u32 },
+    #[error("memory page {page} is missing")]
+    MissingPage { page: u32 },
+    #[error("memory page {page} is not continuous at address {address}")]
+    NonContinuousPage { page: u32, address: U256 },
+    #[error("unknown memory segment {name}")]
+    UnknownSegment { name: String },
+    #[error(
+        "unsupported layout {layout:?}, expected one of {}",
+        Layout::ALL.map(|layout| layout.name()).join(", ")
+    )]
+    UnsupportedLayout { layout: String },
+    #[error("memory segment {name} of layout {layout} is missing")]
+    MissingSegment { layout: Layout, name: String },
+    #[error("no cairo verifier is deployed for layout {layout}")]
+    NoCairoVerifier { layout: Layout },
+    #[error("missing output segment")]
+    MissingOutputSegment,
+    #[error("missing public memory value for address {address}")]
+    MissingMemoryValue { address: u32 },
+    #[error("memory segment {name} stops at {stop_ptr}, before it begins at {begin_addr}")]
+    InvalidSegment {
+        name: String,
+        begin_addr: u32,
+        stop_ptr: u32,
+    },
+    #[error("memory segments {first} and {second} overlap")]
+    OverlappingSegments { first: String, second: String },
+    #[error("n_steps {n_steps} is not a power of two")]
+    NStepsNotPowerOfTwo { n_steps: u32 },
+    #[error("rc_min {rc_min} is above rc_max {rc_max}")]
+    InvalidRangeCheckBounds { rc_min: u32, rc_max: u32 },
+    #[error("invalid public input: {}", join_errors(.violations))]
+    Invalid { violations: Vec<PublicInputError> },
 }
 
-impl From<FromHexError> for ParseError {
-    fn from(_: FromHexError) -> Self {
-        ParseError::InvalidLineFormat
-    }
+fn join_errors(errors: &[PublicInputError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("; ")
 }
 
-impl From<EncodePackedError> for ParseError {
-    fn from(_: EncodePackedError) -> Self {
-        ParseError::EtherEncodingError
-    }
+/// Errors raised when the program output does not match the fact topologies. Offsets and
+/// lengths count from the number of tasks, after the bootloader config if there is one.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TopologyError {
+    #[error("program output has {len} values, too short for the bootloader header")]
+    OutputTooShort { len: usize },
+    #[error("program output value {value} at offset {offset} is not a valid size")]
+    InvalidOutputValue { offset: usize, value: U256 },
+    #[error("task {task}: output index out of bounds")]
+    TaskOutOfBounds { task: usize },
+    #[error("task {task}: task output size exceeds output length")]
+    TaskOutputTooLarge { task: usize },
+    #[error("task {task}: page sizes do not match the task output size")]
+    PageSizesMismatch { task: usize },
+    #[error("not all of the bootloader output was processed: {processed} != {len}")]
+    UnprocessedOutput { processed: usize, len: usize },
+    #[error("task {task}: output is in memory page {page}, expected page {expected}")]
+    UnexpectedPage {
+        task: usize,
+        page: u32,
+        expected: u32,
+    },
+    #[error("task {task}: output spans {n_pages} pages but no tree structure was given")]
+    MissingTreeStructure { task: usize, n_pages: usize },
+    #[error("invalid tree structure: {0}")]
+    InvalidTreeStructure(&'static str),
+}
+
+/// Errors raised when a [MerkleStatement](crate::merkle_statement::MerkleStatement) is checked
+/// offline against the rules of the on-chain `MerkleStatementContract`.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MerkleVerificationError {
+    #[error("merkle height {0} is not below the maximum of 200")]
+    HeightTooLarge(usize),
+    #[error("{n_queries} merkle queries exceed the maximum of {max}")]
+    TooManyQueries { n_queries: usize, max: usize },
+    #[error("cannot split a merkle statement into sub-statements of 0 queries")]
+    EmptySplit,
+    #[error("merkle queue has {indices} indices but {values} values")]
+    QueueLengthMismatch { indices: usize, values: usize },
+    #[error("merkle queue index {index} at position {position} is out of range or not increasing")]
+    InvalidQueueIndex { position: usize, index: U256 },
+    #[error("proof ran out of authentication nodes when looking for sibling node {node}")]
+    MissingAuthenticationNode { node: U256 },
+    /// A supplied authentication node above the leaves differs from any hash the walk could
+    /// recompute for it, since the merkle hash keeps only the 160 most significant bits. `leaf`
+    /// is the queried leaf whose path reads the node.
+    #[error(
+        "authentication node {node} on the path of leaf {leaf} is not a merkle hash: {hash:#x}"
+    )]
+    NodeMismatch { node: U256, leaf: U256, hash: U256 },
+    /// `verifyMerkle` reads an authentication node from the proof only when it cannot compute
+    /// it from the queue, so no internal node is both recomputed and supplied. A wrong leaf, or
+    /// an authentication node that still looks like a hash, therefore only shows at the root.
+    #[error("recomputed merkle root is {computed:#x}, expected {expected:#x}")]
+    RootMismatch { expected: U256, computed: U256 },
+}
+
+/// Errors raised when a [FRIMerkleStatement](crate::fri_merkle_statement::FRIMerkleStatement) is
+/// checked offline against the FRI layer transition of `FriStatementContract.verifyFRI`.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum FriVerificationError {
+    #[error("fri step size {0} exceeds the maximum of {max}", max = crate::fri_merkle_statement::FRI_MAX_STEP_SIZE)]
+    StepSizeTooLarge(usize),
+    #[error("evaluation point {0:#x} is not a field element")]
+    InvalidEvaluationPoint(U256),
+    #[error(
+        "fri queue must be a non-empty list of (query, value, xInv) triplets, got {0} elements"
+    )]
+    InvalidQueueLength(usize),
+    #[error("fri query {query} at position {position} is invalid: {reason}")]
+    InvalidQuery {
+        position: usize,
+        query: U256,
+        reason: &'static str,
+    },
+    #[error("proof ran out of field elements when filling the coset of query {query}")]
+    MissingCosetElement { query: U256 },
+    #[error("computed {computed} output queries but the statement declares {expected}")]
+    OutputLengthMismatch { expected: usize, computed: usize },
+    #[error("output query at position {position} is {computed}, expected {expected}")]
+    OutputQueryMismatch {
+        position: usize,
+        expected: U256,
+        computed: U256,
+    },
+    #[error("folded value of output query {query} is {computed:#x}, expected {expected:#x}")]
+    OutputValueMismatch {
+        query: U256,
+        expected: U256,
+        computed: U256,
+    },
+    #[error("xInv of output query {query} is {computed:#x}, expected {expected:#x}")]
+    OutputInverseMismatch {
+        query: U256,
+        expected: U256,
+        computed: U256,
+    },
+    #[error("fri layer merkle decommitment is invalid: {0}")]
+    Merkle(#[from] MerkleVerificationError),
+}
+
+/// Errors raised by [SplitProofs::verify_locally](crate::annotation_parser::SplitProofs::verify_locally),
+/// naming the statement that failed.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum StatementVerificationError {
+    #[error("merkle statement {name}: {source}")]
+    Merkle {
+        name: String,
+        source: Box<MerkleVerificationError>,
+    },
+    #[error("fri statement for layer {layer}: {source}")]
+    Fri {
+        layer: usize,
+        source: Box<FriVerificationError>,
+    },
+}
+
+/// A verifier to prover value in the annotations that differs from the one recomputed by
+/// [replay_transcript](crate::transcript::replay_transcript).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptMismatch {
+    /// An interaction element, constraint random element or evaluation point.
+    #[error(
+        "annotation line {line}: annotated field element {annotated:#x}, channel computed {computed:#x}"
+    )]
+    FieldElement {
+        line: usize,
+        annotated: U256,
+        computed: U256,
+    },
+    #[error(
+        "annotation line {line}: annotated query index {annotated}, channel computed {computed}"
+    )]
+    QueryIndex {
+        line: usize,
+        annotated: u64,
+        computed: u64,
+    },
+    /// The proof of work nonce does not meet the required number of bits.
+    #[error("annotation line {line}: invalid proof of work nonce")]
+    ProofOfWork { line: usize },
+    /// The annotations list more query indices than the proof parameters allow.
+    #[error("annotation line {line}: more query indices than n_queries")]
+    ExtraQueryIndex { line: usize },
+}
+
+/// Errors raised by the on-chain [Pipeline](crate::submission::Pipeline).
+#[derive(Error, Debug)]
+pub enum SubmissionError {
+    #[error("cannot access state file {path}: {source}")]
+    StateFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("state file {path} is not valid json: {source}")]
+    StateFormat {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error(
+        "state file records facts {recorded:?} for {step}, but this proof produces {expected:?}"
+    )]
+    StateMismatch {
+        step: String,
+        recorded: Vec<ethers::types::H256>,
+        expected: Vec<ethers::types::H256>,
+    },
+    #[error(
+        "merkle statement {name} has {n_queries} queries, above the {max} accepted by \
+         MerkleStatementContract, and the main proof needs the fact of the whole statement"
+    )]
+    MerkleStatementTooLarge {
+        name: String,
+        n_queries: usize,
+        max: usize,
+    },
+    #[error(
+        "the provider is on chain {found}, but the verifier contracts are on chain {expected}"
+    )]
+    ChainIdMismatch { expected: u64, found: U256 },
+    #[error("{step}: contract call failed: {message}")]
+    Contract { step: String, message: String },
+    #[error("{step}: provider error: {message}")]
+    Provider { step: String, message: String },
+    #[error("{step}: transaction {tx_hash:?} was dropped")]
+    Dropped {
+        step: String,
+        tx_hash: ethers::types::H256,
+    },
+    #[error("{step}: transaction {tx_hash:?} reverted")]
+    Reverted {
+        step: String,
+        tx_hash: ethers::types::H256,
+    },
+}
+
+/// Errors raised while deploying the verifier stack on a local chain with
+/// [LocalVerifier](crate::harness::LocalVerifier).
+#[derive(Error, Debug)]
+pub enum DeploymentError {
+    #[error("anvil was not found in PATH, install it with foundry")]
+    AnvilNotFound,
+    #[error("local chain provider error: {0}")]
+    Provider(String),
+    #[error("deployment transaction {name} failed: {message}")]
+    Transaction { name: String, message: String },
+    #[error("deployment transaction {name} reverted in {tx_hash:?}")]
+    Reverted {
+        name: String,
+        tx_hash: ethers::types::H256,
+    },
+    #[error("no code was deployed at the {name} address {address:?}")]
+    MissingCode {
+        name: &'static str,
+        address: ethers::types::Address,
+    },
 }
@@ -0,0 +1,234 @@
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use ethers::types::U256;
+
+/// Little-endian limbs of the cairo prime, see [default_prime](crate::default_prime).
+const P: [u64; 4] = [1, 0, 0, 0x0800000000000011];
+
+/// `-P^-1 mod 2^64`. `P = 1 mod 2^64`, so this is `-1`.
+const P_INV_NEG: u64 = u64::MAX;
+
+/// `R mod P` with `R = 2^256`, the montgomery form of one.
+const R: [u64; 4] = [
+    0xffffffffffffffe1,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x07fffffffffffdf0,
+];
+
+/// `R^2 mod P`, used to bring values into montgomery form.
+const R2: [u64; 4] = [
+    0xfffffd737e000401,
+    0x00000001330fffff,
+    0xffffffffff6f8000,
+    0x07ffd4ab5e008810,
+];
+
+/// An element of the cairo prime field, kept in montgomery form (`value * 2^256 mod P`) so
+/// that multiplication needs no division.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Felt252([u64; 4]);
+
+impl Felt252 {
+    pub const ZERO: Felt252 = Felt252([0; 4]);
+    pub const ONE: Felt252 = Felt252(R);
+
+    /// Interprets `value` as an element already in montgomery form, as used by the verifier
+    /// contracts for field elements in proofs. `value` may be any 256-bit number.
+    pub fn from_montgomery(value: U256) -> Felt252 {
+        // (value * R^-1) * R^2 * R^-1 = value * R^-1 * R, reduced below P
+        Felt252(mont_mul(&mont_mul(&value.0, &[1, 0, 0, 0]), &R2))
+    }
+
+    /// The montgomery form of the element, `value * 2^256 mod P`.
+    pub fn to_montgomery(self) -> U256 {
+        U256(self.0)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    /// Raises the element to the power `exponent`.
+    pub fn pow(self, exponent: U256) -> Felt252 {
+        let mut result = Felt252::ONE;
+        for bit in (0..exponent.bits()).rev() {
+            result *= result;
+            if exponent.bit(bit) {
+                result *= self;
+            }
+        }
+        result
+    }
+
+    /// The multiplicative inverse of the element, or `None` for zero.
+    pub fn inverse(self) -> Option<Felt252> {
+        if self.is_zero() {
+            return None;
+        }
+        Some(self.pow(U256(P) - 2))
+    }
+}
+
+impl From<U256> for Felt252 {
+    /// Reduces `value` modulo the prime.
+    fn from(value: U256) -> Felt252 {
+        // value * R^2 * R^-1 = value * R
+        Felt252(mont_mul(&value.0, &R2))
+    }
+}
+
+impl From<u64> for Felt252 {
+    fn from(value: u64) -> Felt252 {
+        Felt252::from(U256::from(value))
+    }
+}
+
+impl From<Felt252> for U256 {
+    fn from(felt: Felt252) -> U256 {
+        U256(mont_mul(&felt.0, &[1, 0, 0, 0]))
+    }
+}
+
+impl fmt::Debug for Felt252 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Felt252({:#x})", U256::from(*self))
+    }
+}
+
+impl fmt::Display for Felt252 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", U256::from(*self))
+    }
+}
+
+impl Add for Felt252 {
+    type Output = Felt252;
+
+    fn add(self, rhs: Felt252) -> Felt252 {
+        // both operands are below P < 2^252, so the sum cannot overflow
+        let (sum, _) = add_limbs(&self.0, &rhs.0);
+        Felt252(reduce_once(sum))
+    }
+}
+
+impl Sub for Felt252 {
+    type Output = Felt252;
+
+    fn sub(self, rhs: Felt252) -> Felt252 {
+        let (difference, borrow) = sub_limbs(&self.0, &rhs.0);
+        if borrow {
+            Felt252(add_limbs(&difference, &P).0)
+        } else {
+            Felt252(difference)
+        }
+    }
+}
+
+impl Neg for Felt252 {
+    type Output = Felt252;
+
+    fn neg(self) -> Felt252 {
+        Felt252::ZERO - self
+    }
+}
+
+impl Mul for Felt252 {
+    type Output = Felt252;
+
+    fn mul(self, rhs: Felt252) -> Felt252 {
+        Felt252(mont_mul(&self.0, &rhs.0))
+    }
+}
+
+impl AddAssign for Felt252 {
+    fn add_assign(&mut self, rhs: Felt252) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Felt252 {
+    fn sub_assign(&mut self, rhs: Felt252) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Felt252 {
+    fn mul_assign(&mut self, rhs: Felt252) {
+        *self = *self * rhs;
+    }
+}
+
+fn add_limbs(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut result = [0u64; 4];
+    let mut carry = false;
+    for i in 0..4 {
+        let (sum, c1) = a[i].overflowing_add(b[i]);
+        let (sum, c2) = sum.overflowing_add(carry as u64);
+        result[i] = sum;
+        carry = c1 || c2;
+    }
+    (result, carry)
+}
+
+fn sub_limbs(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut result = [0u64; 4];
+    let mut borrow = false;
+    for i in 0..4 {
+        let (difference, b1) = a[i].overflowing_sub(b[i]);
+        let (difference, b2) = difference.overflowing_sub(borrow as u64);
+        result[i] = difference;
+        borrow = b1 || b2;
+    }
+    (result, borrow)
+}
+
+/// Subtracts P from a value below 2P.
+fn reduce_once(value: [u64; 4]) -> [u64; 4] {
+    let (reduced, borrow) = sub_limbs(&value, &P);
+    if borrow {
+        value
+    } else {
+        reduced
+    }
+}
+
+/// Montgomery multiplication `a * b * 2^-256 mod P` (CIOS). The result is fully reduced as long
+/// as `a * b < P * 2^256`, which holds whenever one of the operands is below P.
+fn mont_mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut t = [0u64; 6];
+    for &b_i in b {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let sum = t[j] as u128 + a[j] as u128 * b_i as u128 + carry;
+            t[j] = sum as u64;
+            carry = sum >> 64;
+        }
+        let sum = t[4] as u128 + carry;
+        t[4] = sum as u64;
+        t[5] = (sum >> 64) as u64;
+
+        // add m * P so that the lowest limb becomes zero, then shift it out
+        let m = t[0].wrapping_mul(P_INV_NEG);
+        let mut carry = (t[0] as u128 + m as u128 * P[0] as u128) >> 64;
+        for j in 1..4 {
+            let sum = t[j] as u128 + m as u128 * P[j] as u128 + carry;
+            t[j - 1] = sum as u64;
+            carry = sum >> 64;
+        }
+        let sum = t[4] as u128 + carry;
+        t[3] = sum as u64;
+        t[4] = t[5] + (sum >> 64) as u64;
+    }
+
+    let result = [t[0], t[1], t[2], t[3]];
+    if t[4] != 0 {
+        // only reachable when neither operand is below P; the subtraction wraps below 2^256
+        sub_limbs(&result, &P).0
+    } else {
+        reduce_once(result)
+    }
+}
@@ -3,15 +3,25 @@ use std::sync::Arc;
 use ethers::{
     abi::Address,
     contract::abigen,
-    core::k256::ecdsa::SigningKey,
-    middleware::SignerMiddleware,
-    providers::{Http, Provider},
-    signers::Wallet,
-    types::U256,
+    providers::Middleware,
+    types::{H256, U256},
 };
 use serde::{Deserialize, Serialize};
 
-use crate::ContractFunctionCall;
+use crate::{
+    default_prime,
+    errors::FriVerificationError,
+    field::Felt252,
+    keccak_words,
+    merkle_statement::{commitment_mask, compute_merkle_root},
+    ContractFunctionCall,
+};
+
+/// Maximum `fri_step_size` accepted by `FriStatementContract`.
+pub const FRI_MAX_STEP_SIZE: usize = 4;
+
+/// Generator of the multiplicative group of the cairo prime field.
+const FIELD_GENERATOR: u64 = 3;
 
 /// Decommitment for a FRI layer merkle statement
 #[derive(Serialize, Deserialize, Debug)]
@@ -53,15 +63,227 @@ impl FRIMerkleStatement {
         }
     }
 
+    /// Fact registered by `FriStatementContract.verifyFRI` for this statement:
+    /// keccak256(evaluation_point, fri_step_size, keccak256(input queue), keccak256(output queue), expected_root).
+    pub fn fact_hash(&self) -> H256 {
+        let input_hash = keccak_words(&self.input_interleaved);
+        let output_hash = keccak_words(&self.output_interleaved);
+        keccak_words(&[
+            self.evaluation_point,
+            U256::from(self.fri_step_size),
+            U256::from_big_endian(input_hash.as_bytes()),
+            U256::from_big_endian(output_hash.as_bytes()),
+            self.expected_root,
+        ])
+    }
+
+    /// Recomputes the FRI layer transition of `FriStatementContract.verifyFRI`: folds the cosets
+    /// of `input_interleaved` with `evaluation_point`, compares the result with
+    /// `output_interleaved` and checks the coset hashes against `expected_root`.
+    pub fn verify_locally(&self) -> Result<(), FriVerificationError> {
+        let prime = default_prime();
+        if self.fri_step_size > FRI_MAX_STEP_SIZE {
+            return Err(FriVerificationError::StepSizeTooLarge(self.fri_step_size));
+        }
+        if self.evaluation_point >= prime {
+            return Err(FriVerificationError::InvalidEvaluationPoint(
+                self.evaluation_point,
+            ));
+        }
+        let inputs = triplets(&self.input_interleaved)?;
+        validate_queries(&inputs, prime)?;
+
+        let coset_size = 1usize << self.fri_step_size;
+        let coset_mask = !(U256::from(coset_size) - 1);
+        let group_inverse = Felt252::from(FIELD_GENERATOR)
+            .pow((prime - 1) / U256::from(coset_size))
+            .inverse()
+            .expect("the group generator is not zero");
+
+        let mut outputs = Vec::new();
+        let mut merkle_queue = Vec::new();
+        let mut n_consumed = 0;
+        let mut i = 0;
+        while i < inputs.len() {
+            let (first_query, _, first_x_inv) = inputs[i];
+            let coset_idx = first_query & coset_mask;
+
+            // Fill the coset with values from the queue, or from the proof when not queried.
+            let mut evaluations = Vec::with_capacity(coset_size);
+            for offset in 0..coset_size {
+                let index = coset_idx + offset;
+                if i < inputs.len() && inputs[i].0 == index {
+                    evaluations.push(inputs[i].1);
+                    i += 1;
+                } else {
+                    let element = self
+                        .proof
+                        .get(n_consumed)
+                        .ok_or(FriVerificationError::MissingCosetElement { query: index })?;
+                    evaluations.push(*element % prime);
+                    n_consumed += 1;
+                }
+            }
+
+            let output_query = coset_idx / U256::from(coset_size);
+            merkle_queue.push((output_query, hash_coset(&evaluations)));
+
+            // xInv of the first query is c^-1 * g^-bitrev(offset), recover c^-1.
+            let offset = (first_query - coset_idx).as_usize();
+            let coset_offset_inverse = Felt252::from(first_x_inv)
+                * group_inverse
+                    .pow(U256::from(bit_reverse(offset, self.fri_step_size)))
+                    .inverse()
+                    .expect("a power of the group generator is not zero");
+            let (value, x_inv) = fold_coset(
+                evaluations.into_iter().map(Felt252::from).collect(),
+                coset_offset_inverse,
+                group_inverse,
+                Felt252::from(self.evaluation_point),
+            );
+            outputs.push((output_query, value.into(), x_inv.into()));
+        }
+
+        let expected_outputs = triplets(&self.output_interleaved)?;
+        if expected_outputs.len() != outputs.len() {
+            return Err(FriVerificationError::OutputLengthMismatch {
+                expected: expected_outputs.len(),
+                computed: outputs.len(),
+            });
+        }
+        for (position, (expected, computed)) in expected_outputs.iter().zip(&outputs).enumerate() {
+            if expected.0 != computed.0 {
+                return Err(FriVerificationError::OutputQueryMismatch {
+                    position,
+                    expected: expected.0,
+                    computed: computed.0,
+                });
+            }
+            if expected.1 != computed.1 {
+                return Err(FriVerificationError::OutputValueMismatch {
+                    query: computed.0,
+                    expected: expected.1,
+                    computed: computed.1,
+                });
+            }
+            if expected.2 != computed.2 {
+                return Err(FriVerificationError::OutputInverseMismatch {
+                    query: computed.0,
+                    expected: expected.2,
+                    computed: computed.2,
+                });
+            }
+        }
+
+        compute_merkle_root(&merkle_queue, &self.proof[n_consumed..], self.expected_root)?;
+        Ok(())
+    }
+
     /// Initiates `verifyFRI` contract function call
-    pub fn verify(
+    pub fn verify<M: Middleware>(
         &self,
         address: Address,
-        signer: Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
-    ) -> ContractFunctionCall {
+        signer: Arc<M>,
+    ) -> ContractFunctionCall<M> {
         let contract = FriStatementContract::new(address, signer);
 
         let call = self.contract_function_call();
-        contract.method("verifyFRI", call).unwrap()
+        contract.verify_fri(
+            call.proof,
+            call.fri_queue,
+            call.evaluation_point,
+            call.fri_step_size,
+            call.expected_root,
+        )
+    }
+}
+
+/// Splits an interleaved fri queue into (query, value, xInv) triplets.
+fn triplets(interleaved: &[U256]) -> Result<Vec<(U256, U256, U256)>, FriVerificationError> {
+    if interleaved.is_empty() || !interleaved.len().is_multiple_of(3) {
+        return Err(FriVerificationError::InvalidQueueLength(interleaved.len()));
+    }
+    Ok(interleaved
+        .chunks(3)
+        .map(|triplet| (triplet[0], triplet[1], triplet[2]))
+        .collect())
+}
+
+/// Applies the input checks of `verifyFRI`: queries strictly increasing and on the same layer,
+/// values and inverses in the field.
+fn validate_queries(
+    inputs: &[(U256, U256, U256)],
+    prime: U256,
+) -> Result<(), FriVerificationError> {
+    let mut prev_query = U256::zero();
+    for (position, &(query, value, x_inv)) in inputs.iter().enumerate() {
+        let reason = if query <= prev_query {
+            Some("queries must be strictly increasing")
+        } else if value >= prime {
+            Some("value is not a field element")
+        } else if x_inv >= prime {
+            Some("xInv is not a field element")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(FriVerificationError::InvalidQuery {
+                position,
+                query,
+                reason,
+            });
+        }
+        prev_query = query;
+    }
+
+    let first = inputs[0].0;
+    let last = inputs[inputs.len() - 1].0;
+    if (first ^ last) >= first {
+        return Err(FriVerificationError::InvalidQuery {
+            position: inputs.len() - 1,
+            query: last,
+            reason: "queries are not on the same layer",
+        });
+    }
+    Ok(())
+}
+
+/// Merkle leaf of a coset: keccak256 of its evaluations, masked like every merkle node.
+fn hash_coset(evaluations: &[U256]) -> U256 {
+    U256::from_big_endian(keccak_words(evaluations).as_bytes()) & commitment_mask()
+}
+
+/// Folds the evaluations on a coset, in bit-reversed order, down to a single value.
+/// Each step computes `f(x) + f(-x) + eval_point * x^-1 * (f(x) - f(-x))` and squares the
+/// coset offset, the group generator and the evaluation point.
+/// Returns the folded value and the xInv of the point it belongs to.
+fn fold_coset(
+    mut evaluations: Vec<Felt252>,
+    mut coset_offset_inverse: Felt252,
+    mut group_inverse: Felt252,
+    mut eval_point: Felt252,
+) -> (Felt252, Felt252) {
+    while evaluations.len() > 1 {
+        let log_len = evaluations.len().trailing_zeros() as usize;
+        evaluations = evaluations
+            .chunks(2)
+            .enumerate()
+            .map(|(m, pair)| {
+                let x_inv = coset_offset_inverse
+                    * group_inverse.pow(U256::from(bit_reverse(2 * m, log_len)));
+                pair[0] + pair[1] + eval_point * x_inv * (pair[0] - pair[1])
+            })
+            .collect();
+        coset_offset_inverse *= coset_offset_inverse;
+        group_inverse *= group_inverse;
+        eval_point *= eval_point;
+    }
+    (evaluations[0], coset_offset_inverse)
+}
+
+fn bit_reverse(value: usize, n_bits: usize) -> usize {
+    if n_bits == 0 {
+        return 0;
     }
+    value.reverse_bits() >> (usize::BITS as usize - n_bits)
 }
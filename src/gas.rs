@@ -0,0 +1,273 @@
+use std::fmt;
+
+use ethers::{
+    providers::{Middleware, MiddlewareError},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+    utils::format_units,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{AdapterError, SubmissionError},
+    submission::{Pipeline, Step},
+};
+
+/// Gas charged for every transaction before any calldata or execution.
+pub const TX_BASE_GAS: u64 = 21_000;
+
+/// Gas charged per zero byte of calldata.
+pub const CALLDATA_ZERO_BYTE_GAS: u64 = 4;
+
+/// Gas charged per non-zero byte of calldata.
+pub const CALLDATA_NONZERO_BYTE_GAS: u64 = 16;
+
+/// Execution cost of a contract call, linear in the number of 32-byte words of its arguments.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecutionModel {
+    /// Cost independent of the arguments, mostly the storage write registering the fact.
+    pub fixed: u64,
+    /// Cost per argument word, such as hashing a merkle node or folding a FRI coset element.
+    pub per_word: u64,
+}
+
+impl ExecutionModel {
+    fn gas(&self, calldata: &[u8]) -> u64 {
+        // Skip the function selector
+        let n_words = calldata.len().saturating_sub(4) / 32;
+        self.fixed + self.per_word * n_words as u64
+    }
+}
+
+/// Offline gas model of the verifier contracts: the intrinsic cost of the transaction and its
+/// calldata, plus an [ExecutionModel] per contract function.
+///
+/// The default figures are a budget derived from the EVM gas schedule, not measurements, and
+/// err on the high side:
+/// - the fixed cost of a statement or memory page is about twice the 22,100 gas of the cold
+///   `SSTORE` registering its fact, to cover the call, the ABI decoding and the emitted event;
+/// - the per-word cost allows for a few `keccak256` and memory operations on every argument
+///   word, which is what hashing merkle nodes or folding FRI cosets does;
+/// - the fixed cost of the main proof covers the out of domain checks and the channel replay of
+///   `GpsStatementVerifier`, which are independent of the argument size.
+///
+/// For precise values, use [estimate] against a node or a fork of the target network, which
+/// measures every call with `eth_estimateGas`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasModel {
+    pub merkle_statement: ExecutionModel,
+    pub fri_statement: ExecutionModel,
+    pub continuous_memory_page: ExecutionModel,
+    pub main_proof: ExecutionModel,
+}
+
+impl Default for GasModel {
+    fn default() -> Self {
+        GasModel {
+            merkle_statement: ExecutionModel {
+                fixed: 45_000,
+                per_word: 350,
+            },
+            fri_statement: ExecutionModel {
+                fixed: 60_000,
+                per_word: 900,
+            },
+            continuous_memory_page: ExecutionModel {
+                fixed: 45_000,
+                per_word: 300,
+            },
+            main_proof: ExecutionModel {
+                fixed: 1_500_000,
+                per_word: 900,
+            },
+        }
+    }
+}
+
+impl GasModel {
+    /// Estimated gas of sending `calldata` for `step`.
+    pub fn gas(&self, step: &Step, calldata: &[u8]) -> U256 {
+        let execution = match step {
+            Step::MerkleStatement(_) => &self.merkle_statement,
+            Step::FriStatement(_) => &self.fri_statement,
+            Step::ContinuousMemoryPage(_) => &self.continuous_memory_page,
+            Step::MainProof => &self.main_proof,
+        };
+        U256::from(TX_BASE_GAS + calldata_gas(calldata) + execution.gas(calldata))
+    }
+}
+
+/// Gas charged for `calldata` itself.
+pub fn calldata_gas(calldata: &[u8]) -> u64 {
+    calldata
+        .iter()
+        .map(|&byte| {
+            if byte == 0 {
+                CALLDATA_ZERO_BYTE_GAS
+            } else {
+                CALLDATA_NONZERO_BYTE_GAS
+            }
+        })
+        .sum()
+}
+
+/// Where the gas figure of a [CallEstimate] comes from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum GasSource {
+    /// The offline [GasModel].
+    Model,
+    /// `eth_estimateGas` on a node.
+    Node,
+    /// `eth_estimateGas` reverted with this message, and the [GasModel] was used instead. This is
+    /// expected for calls that need the facts of earlier steps, such as the main proof, when
+    /// those steps were not sent yet.
+    ModelFallback(String),
+}
+
+/// Estimated gas of a single step of the submission.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CallEstimate {
+    pub step: String,
+    pub to: Address,
+    pub calldata_len: usize,
+    pub gas: U256,
+    pub source: GasSource,
+}
+
+/// Estimated gas of every step of a submission, in the order they are sent.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GasReport {
+    pub calls: Vec<CallEstimate>,
+}
+
+impl GasReport {
+    /// Gas of the whole submission.
+    pub fn total_gas(&self) -> U256 {
+        self.calls
+            .iter()
+            .fold(U256::zero(), |total, call| total + call.gas)
+    }
+
+    /// Cost in wei of the whole submission at `gas_price` wei per gas.
+    pub fn cost(&self, gas_price: U256) -> U256 {
+        self.total_gas() * gas_price
+    }
+
+    /// Renders the report as a table with a total row, and the cost at `gas_price` wei per gas.
+    pub fn table(&self, gas_price: U256) -> String {
+        // Formatting whole wei amounts in ether or gwei cannot fail
+        let cost = self.cost(gas_price);
+        let eth = format_units(cost, "ether").unwrap_or_else(|_| format!("{} wei", cost));
+        let gwei = format_units(gas_price, "gwei").unwrap_or_else(|_| format!("{} wei", gas_price));
+        format!("{}Cost at {} gwei: {} ETH\n", self, gwei, eth)
+    }
+}
+
+impl fmt::Display for GasReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name_width = self
+            .calls
+            .iter()
+            .map(|call| call.step.len())
+            .chain(std::iter::once("Total".len()))
+            .max()
+            .unwrap_or(0);
+
+        writeln!(
+            f,
+            "{:<name_width$}  {:>14}  {:>12}  source",
+            "step", "calldata bytes", "gas"
+        )?;
+        for call in &self.calls {
+            let source = match &call.source {
+                GasSource::Model => "model",
+                GasSource::Node => "node",
+                GasSource::ModelFallback(_) => "model (node estimate reverted)",
+            };
+            writeln!(
+                f,
+                "{:<name_width$}  {:>14}  {:>12}  {}",
+                call.step, call.calldata_len, call.gas, source
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<name_width$}  {:>14}  {:>12}",
+            "Total",
+            self.calls
+                .iter()
+                .map(|call| call.calldata_len)
+                .sum::<usize>(),
+            self.total_gas()
+        )
+    }
+}
+
+/// Estimates the gas of every step of `pipeline` offline, from the calldata and `model`.
+pub fn estimate_offline(pipeline: &Pipeline, model: &GasModel) -> Result<GasReport, AdapterError> {
+    let calls = pipeline
+        .steps()
+        .into_iter()
+        .map(|step| {
+            let calldata = pipeline.calldata(&step)?;
+            Ok(CallEstimate {
+                step: step.name(),
+                to: pipeline.target(&step),
+                calldata_len: calldata.len(),
+                gas: model.gas(&step, &calldata),
+                source: GasSource::Model,
+            })
+        })
+        .collect::<Result<Vec<CallEstimate>, AdapterError>>()?;
+    Ok(GasReport { calls })
+}
+
+/// Estimates the gas of every step of `pipeline` with `eth_estimateGas` on `client`, sending
+/// from `from` when given. Steps whose estimate reverts fall back to `model`, see
+/// [GasSource::ModelFallback]. Any other provider error, such as an unreachable node, fails the
+/// estimate with [SubmissionError::Provider], as does a `client` that is not on the chain of the
+/// pipeline, see [Pipeline::check_chain_id].
+pub async fn estimate<M: Middleware>(
+    pipeline: &Pipeline,
+    client: &M,
+    from: Option<Address>,
+    model: &GasModel,
+) -> Result<GasReport, AdapterError> {
+    pipeline.check_chain_id(client).await?;
+    let mut calls = Vec::new();
+    for step in pipeline.steps() {
+        let calldata = pipeline.calldata(&step)?;
+        let to = pipeline.target(&step);
+        let tx = call_transaction(to, from, calldata.clone());
+
+        let (gas, source) = match client.estimate_gas(&tx, None).await {
+            Ok(gas) => (gas, GasSource::Node),
+            Err(e) if e.as_error_response().is_some_and(|e| e.is_revert()) => (
+                model.gas(&step, &calldata),
+                GasSource::ModelFallback(e.to_string()),
+            ),
+            Err(e) => {
+                return Err(SubmissionError::Provider {
+                    step: step.name(),
+                    message: e.to_string(),
+                }
+                .into())
+            }
+        };
+        calls.push(CallEstimate {
+            step: step.name(),
+            to,
+            calldata_len: calldata.len(),
+            gas,
+            source,
+        });
+    }
+    Ok(GasReport { calls })
+}
+
+fn call_transaction(to: Address, from: Option<Address>, calldata: Bytes) -> TypedTransaction {
+    let mut tx = TransactionRequest::new().to(to).data(calldata);
+    if let Some(from) = from {
+        tx = tx.from(from);
+    }
+    tx.into()
+}
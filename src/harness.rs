@@ -0,0 +1,141 @@
+use std::{fs::File, io::BufReader, path::Path, process::Command, sync::Arc};
+
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    middleware::SignerMiddleware,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes, TransactionRequest, U64},
+    utils::{Anvil, AnvilInstance},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{AdapterError, DeploymentError, ParseError},
+    submission::ContractAddresses,
+};
+
+/// Gas limit of the blocks of the local chain, above mainnet so that a whole proof fits.
+const LOCAL_BLOCK_GAS_LIMIT: u64 = 100_000_000;
+
+/// A recorded deployment of the Starkware verifier stack: `MerkleStatementContract`,
+/// `FriStatementContract`, `MemoryPageFactRegistry` and `GpsStatementVerifier` with its Cairo
+/// verifiers.
+///
+/// The transactions are replayed in order from the first Anvil dev account on a fresh chain, so
+/// contract creations land at the same addresses as when the deployment was recorded.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VerifierDeployment {
+    /// Addresses of the contracts the split proofs are submitted to, once deployed.
+    pub contracts: ContractAddresses,
+    pub transactions: Vec<DeploymentTransaction>,
+}
+
+/// A contract creation, when `to` is not set, or a configuration call of the deployment.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeploymentTransaction {
+    /// Name of the deployed contract or of the call, used in errors.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    /// Creation bytecode with its ABI-encoded constructor arguments, or calldata.
+    pub data: Bytes,
+}
+
+impl VerifierDeployment {
+    /// Reads a deployment json file.
+    pub fn load(path: impl AsRef<Path>) -> Result<VerifierDeployment, AdapterError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| AdapterError::File {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Ok(serde_json::from_reader(BufReader::new(file)).map_err(ParseError::from)?)
+    }
+}
+
+/// The verifier stack deployed on a local Anvil chain with no fork, for running a full
+/// submission offline. The chain stops when this is dropped.
+pub struct LocalVerifier {
+    pub contracts: ContractAddresses,
+    /// Client signing with the first Anvil dev account, which deployed the stack.
+    pub client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    anvil: AnvilInstance,
+}
+
+impl LocalVerifier {
+    /// Spawns Anvil and replays `deployment` on it.
+    pub async fn deploy(deployment: &VerifierDeployment) -> Result<LocalVerifier, AdapterError> {
+        if Command::new("anvil").arg("--version").output().is_err() {
+            return Err(DeploymentError::AnvilNotFound.into());
+        }
+        let anvil = Anvil::new()
+            .args(["--gas-limit", &LOCAL_BLOCK_GAS_LIMIT.to_string()])
+            .arg("--disable-code-size-limit")
+            .spawn();
+
+        let provider = Provider::<Http>::try_from(anvil.endpoint())
+            .map_err(|e| DeploymentError::Provider(e.to_string()))?;
+        let wallet = LocalWallet::from(SigningKey::from(anvil.keys()[0].clone()))
+            .with_chain_id(anvil.chain_id());
+        let client = Arc::new(SignerMiddleware::new(provider, wallet));
+
+        for transaction in &deployment.transactions {
+            let mut tx = TransactionRequest::new()
+                .data(transaction.data.clone())
+                .gas(LOCAL_BLOCK_GAS_LIMIT);
+            if let Some(to) = transaction.to {
+                tx = tx.to(to);
+            }
+
+            let send_error = |e: String| DeploymentError::Transaction {
+                name: transaction.name.clone(),
+                message: e,
+            };
+            let receipt = client
+                .send_transaction(tx, None)
+                .await
+                .map_err(|e| send_error(e.to_string()))?
+                .await
+                .map_err(|e| send_error(e.to_string()))?
+                .ok_or_else(|| send_error("transaction was dropped".to_string()))?;
+            if receipt.status != Some(U64::from(1)) {
+                return Err(DeploymentError::Reverted {
+                    name: transaction.name.clone(),
+                    tx_hash: receipt.transaction_hash,
+                }
+                .into());
+            }
+        }
+
+        let contracts = &deployment.contracts;
+        for (name, address) in [
+            ("merkle statement", contracts.merkle_statement),
+            ("fri statement", contracts.fri_statement),
+            (
+                "memory page fact registry",
+                contracts.memory_page_fact_registry,
+            ),
+            ("gps statement verifier", contracts.gps_statement_verifier),
+        ] {
+            let code = client
+                .get_code(address, None)
+                .await
+                .map_err(|e| DeploymentError::Provider(e.to_string()))?;
+            if code.is_empty() {
+                return Err(DeploymentError::MissingCode { name, address }.into());
+            }
+        }
+
+        Ok(LocalVerifier {
+            contracts: deployment.contracts.clone(),
+            client,
+            anvil,
+        })
+    }
+
+    /// HTTP endpoint of the local chain.
+    pub fn endpoint(&self) -> String {
+        self.anvil.endpoint()
+    }
+}
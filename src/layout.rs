@@ -0,0 +1,167 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PublicInputError;
+
+/// Cairo layouts the stone prover can generate proofs for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    Plain,
+    Small,
+    Dex,
+    Recursive,
+    Starknet,
+    StarknetWithKeccak,
+    RecursiveLargeOutput,
+    RecursiveWithPoseidon,
+    AllSolidity,
+    Dynamic,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 10] = [
+        Layout::Plain,
+        Layout::Small,
+        Layout::Dex,
+        Layout::Recursive,
+        Layout::Starknet,
+        Layout::StarknetWithKeccak,
+        Layout::RecursiveLargeOutput,
+        Layout::RecursiveWithPoseidon,
+        Layout::AllSolidity,
+        Layout::Dynamic,
+    ];
+
+    /// The layout name, as found in the `layout` field of the public input.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Plain => "plain",
+            Layout::Small => "small",
+            Layout::Dex => "dex",
+            Layout::Recursive => "recursive",
+            Layout::Starknet => "starknet",
+            Layout::StarknetWithKeccak => "starknet_with_keccak",
+            Layout::RecursiveLargeOutput => "recursive_large_output",
+            Layout::RecursiveWithPoseidon => "recursive_with_poseidon",
+            Layout::AllSolidity => "all_solidity",
+            Layout::Dynamic => "dynamic",
+        }
+    }
+
+    /// Memory segments of the layout, in the order the cairo verifier reads their bounds from
+    /// the public input: program and execution, followed by the builtins of the layout.
+    pub fn segment_names(&self) -> &'static [&'static str] {
+        match self {
+            Layout::Plain => &["program", "execution", "output"],
+            Layout::Small | Layout::Dex => &[
+                "program",
+                "execution",
+                "output",
+                "pedersen",
+                "range_check",
+                "ecdsa",
+            ],
+            Layout::Recursive => &[
+                "program",
+                "execution",
+                "output",
+                "pedersen",
+                "range_check",
+                "bitwise",
+            ],
+            Layout::Starknet => &[
+                "program",
+                "execution",
+                "output",
+                "pedersen",
+                "range_check",
+                "ecdsa",
+                "bitwise",
+                "ec_op",
+                "poseidon",
+            ],
+            Layout::StarknetWithKeccak => &[
+                "program",
+                "execution",
+                "output",
+                "pedersen",
+                "range_check",
+                "ecdsa",
+                "bitwise",
+                "ec_op",
+                "keccak",
+                "poseidon",
+            ],
+            Layout::RecursiveLargeOutput | Layout::RecursiveWithPoseidon => &[
+                "program",
+                "execution",
+                "output",
+                "pedersen",
+                "range_check",
+                "bitwise",
+                "poseidon",
+            ],
+            Layout::AllSolidity => &[
+                "program",
+                "execution",
+                "output",
+                "pedersen",
+                "range_check",
+                "ecdsa",
+                "bitwise",
+                "ec_op",
+            ],
+            Layout::Dynamic => &[
+                "program",
+                "execution",
+                "output",
+                "pedersen",
+                "range_check",
+                "ecdsa",
+                "bitwise",
+                "ec_op",
+                "keccak",
+                "poseidon",
+                "range_check96",
+                "add_mod",
+                "mul_mod",
+            ],
+        }
+    }
+
+    /// Index of the layout's cairo verifier in `cairoVerifierContractAddresses` of the deployed
+    /// `GpsStatementVerifier`, or `None` when no verifier is deployed for the layout.
+    pub fn cairo_verifier_id(&self) -> Option<u64> {
+        match self {
+            Layout::Small => Some(1),
+            Layout::Dex => Some(2),
+            Layout::Recursive => Some(3),
+            Layout::StarknetWithKeccak => Some(4),
+            Layout::RecursiveLargeOutput => Some(5),
+            Layout::Starknet => Some(6),
+            Layout::RecursiveWithPoseidon => Some(7),
+            Layout::Plain | Layout::AllSolidity | Layout::Dynamic => None,
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = PublicInputError;
+
+    fn from_str(name: &str) -> Result<Layout, PublicInputError> {
+        Layout::ALL
+            .into_iter()
+            .find(|layout| layout.name() == name)
+            .ok_or_else(|| PublicInputError::UnsupportedLayout {
+                layout: name.to_string(),
+            })
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
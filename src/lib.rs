@@ -8,15 +8,25 @@ use ethers::{
     middleware::SignerMiddleware,
     providers::{Http, Provider},
     signers::Wallet,
-    types::U256,
+    types::{H256, U256},
+    utils::keccak256,
 };
 
 pub mod annotated_proof;
+pub mod annotation;
 pub mod annotation_parser;
+pub mod calldata;
 pub mod errors;
+pub mod field;
 pub mod fri_merkle_statement;
+pub mod gas;
+pub mod harness;
+pub mod layout;
 pub mod merkle_statement;
+pub mod network;
 pub mod oods_statement;
+pub mod submission;
+pub mod transcript;
 
 /// Default prime field for cairo. This prime will be used when modular operations are needed.
 pub fn default_prime() -> U256 {
@@ -25,9 +35,19 @@ pub fn default_prime() -> U256 {
         + U256::from(1)
 }
 
-/// A type alias for ethers contract function call.
-pub type ContractFunctionCall = FunctionCall<
-    Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
-    SignerMiddleware<Provider<Http>, Wallet<SigningKey>>,
-    (),
->;
+/// Keccak256 of values laid out as consecutive 32-byte big-endian words, the way the verifier
+/// contracts hash `uint256` arrays in memory.
+pub(crate) fn keccak_words(values: &[U256]) -> H256 {
+    let mut data = vec![0u8; 32 * values.len()];
+    for (chunk, value) in data.chunks_mut(32).zip(values) {
+        value.to_big_endian(chunk);
+    }
+    H256::from(keccak256(data))
+}
+
+/// The concrete middleware used before the contract wrappers became generic: a local wallet
+/// signing transactions sent over HTTP.
+pub type HttpSignerMiddleware = SignerMiddleware<Provider<Http>, Wallet<SigningKey>>;
+
+/// A type alias for ethers contract function call, generic over the middleware `M` sending it.
+pub type ContractFunctionCall<M = HttpSignerMiddleware> = FunctionCall<Arc<M>, M, ()>;
@@ -1,16 +1,22 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
 
 use ethers::{
     contract::abigen,
-    core::k256::ecdsa::SigningKey,
-    middleware::SignerMiddleware,
-    providers::{Http, Provider},
-    signers::Wallet,
-    types::{Address, U256},
+    providers::Middleware,
+    types::{Address, H256, U256},
 };
 use serde::{Deserialize, Serialize};
 
-use crate::ContractFunctionCall;
+use crate::{errors::MerkleVerificationError, keccak_words, ContractFunctionCall};
+
+/// Maximum number of queries accepted by the on-chain `MerkleVerifier`.
+pub const MAX_N_MERKLE_VERIFIER_QUERIES: usize = 128;
+
+/// Maximum merkle height (exclusive) accepted by `MerkleStatementContract`.
+pub const MAX_MERKLE_HEIGHT: usize = 200;
 
 /// Decommitment for a merkle statement
 #[derive(Serialize, Deserialize, Debug)]
@@ -69,15 +75,252 @@ impl MerkleStatement {
         }
     }
 
+    /// Fact registered by `MerkleStatementContract.verifyMerkle` for this statement:
+    /// keccak256 of the merkle queue (index, value pairs) followed by the root.
+    pub fn fact_hash(&self) -> H256 {
+        let mut data = self.merkle_queue();
+        data.push(self.expected_root);
+        keccak_words(&data)
+    }
+
+    /// Recomputes the merkle root from the queue and the authentication nodes in `proof`, the same
+    /// way `MerkleStatementContract.verifyMerkle` does, and checks it against `expected_root`.
+    pub fn verify_locally(&self) -> Result<(), MerkleVerificationError> {
+        if self.merkle_height >= MAX_MERKLE_HEIGHT {
+            return Err(MerkleVerificationError::HeightTooLarge(self.merkle_height));
+        }
+        if self.merkle_queue_indices.len() > MAX_N_MERKLE_VERIFIER_QUERIES {
+            return Err(MerkleVerificationError::TooManyQueries {
+                n_queries: self.merkle_queue_indices.len(),
+                max: MAX_N_MERKLE_VERIFIER_QUERIES,
+            });
+        }
+        self.verify_queue(&mut HashMap::new())
+    }
+
+    /// Checks the queue and recomputes the root like [verify_locally](Self::verify_locally),
+    /// without the limit on the number of queries, recording the hash of every node visited.
+    fn verify_queue(&self, nodes: &mut HashMap<U256, U256>) -> Result<(), MerkleVerificationError> {
+        if self.merkle_height >= MAX_MERKLE_HEIGHT {
+            return Err(MerkleVerificationError::HeightTooLarge(self.merkle_height));
+        }
+        if self.merkle_queue_indices.len() != self.merkle_queue_values.len() {
+            return Err(MerkleVerificationError::QueueLengthMismatch {
+                indices: self.merkle_queue_indices.len(),
+                values: self.merkle_queue_values.len(),
+            });
+        }
+
+        // Indices must be strictly increasing and lie in [2**height, 2**(height+1)).
+        let mut lower_limit = U256::one() << self.merkle_height;
+        let upper_limit = U256::one() << (self.merkle_height + 1);
+        for (position, &index) in self.merkle_queue_indices.iter().enumerate() {
+            if index < lower_limit || index >= upper_limit {
+                return Err(MerkleVerificationError::InvalidQueueIndex { position, index });
+            }
+            lower_limit = index + 1;
+        }
+
+        walk_merkle_tree(&self.queue(), &self.proof, self.expected_root, nodes)?;
+        Ok(())
+    }
+
+    /// Number of queries in the merkle queue.
+    pub fn n_queries(&self) -> usize {
+        self.merkle_queue_indices.len()
+    }
+
+    /// Splits the statement into sub-statements of at most `max_queries` consecutive queries
+    /// (capped to [MAX_N_MERKLE_VERIFIER_QUERIES]), each with the authentication nodes its own
+    /// queries need, so that every sub-statement passes `verifyMerkle` on its own.
+    ///
+    /// The sub-statements register their own facts, not the fact of this statement.
+    /// `GpsStatementVerifier` checks the trace decommitments against the [fact_hash] of the whole
+    /// queue of each commitment, as produced by
+    /// [split_fri_merkle_statements](crate::annotation_parser::split_fri_merkle_statements), so the
+    /// main proof is only accepted once the unsplit statement is registered. Splitting only helps
+    /// callers whose contracts check the facts of the sub-statements, and
+    /// [Pipeline](crate::submission::Pipeline) never splits.
+    ///
+    /// [fact_hash]: MerkleStatement::fact_hash
+    pub fn split(
+        &self,
+        max_queries: usize,
+    ) -> Result<Vec<MerkleStatement>, MerkleVerificationError> {
+        if max_queries == 0 {
+            return Err(MerkleVerificationError::EmptySplit);
+        }
+        let max_queries = max_queries.min(MAX_N_MERKLE_VERIFIER_QUERIES);
+
+        // Every sibling met on the path of a query is either computed from the queue or read
+        // from the proof when verifying the whole statement.
+        let mut nodes = HashMap::new();
+        self.verify_queue(&mut nodes)?;
+
+        self.queue()
+            .chunks(max_queries)
+            .map(|chunk| {
+                let proof = authentication_nodes(chunk, &nodes)?;
+                let statement = MerkleStatement::new(
+                    self.expected_root,
+                    chunk.len(),
+                    self.merkle_height,
+                    chunk.iter().map(|&(index, _)| index).collect(),
+                    chunk.iter().map(|&(_, value)| value).collect(),
+                    proof,
+                );
+                statement.verify_locally()?;
+                Ok(statement)
+            })
+            .collect()
+    }
+
+    fn queue(&self) -> Vec<(U256, U256)> {
+        self.merkle_queue_indices
+            .iter()
+            .cloned()
+            .zip(self.merkle_queue_values.iter().cloned())
+            .collect()
+    }
+
     /// Initiates `verifyMerkle` contract call.
-    pub fn verify(
+    pub fn verify<M: Middleware>(
         &self,
         address: Address,
-        signer: Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
-    ) -> ContractFunctionCall {
+        signer: Arc<M>,
+    ) -> ContractFunctionCall<M> {
         let contract = MerkleStatementContract::new(address, signer);
 
-        let verify_merkle_call = self.contract_function_call();
-        contract.method("verifyMerkle", verify_merkle_call).unwrap()
+        let call = self.contract_function_call();
+        contract.verify_merkle(
+            call.proof,
+            call.merkle_queue,
+            call.merkle_height,
+            call.expected_root,
+        )
+    }
+}
+
+/// Hash used by the on-chain `MerkleVerifier`: keccak256 of the two children, keeping only the
+/// 160 most significant bits.
+pub(crate) fn merkle_hash(left: U256, right: U256) -> U256 {
+    U256::from_big_endian(keccak_words(&[left, right]).as_bytes()) & commitment_mask()
+}
+
+pub(crate) fn commitment_mask() -> U256 {
+    !((U256::one() << 96) - 1)
+}
+
+/// Walks the queue of `(node index, hash)` pairs up to the root, taking siblings from the queue
+/// when present and from `proof` otherwise, as `MerkleVerifier.verifyMerkle` does.
+/// Returns the number of authentication nodes read from `proof`.
+///
+/// Every authentication node above the leaves is itself a [merkle_hash], so one with any of the
+/// bits cleared by [commitment_mask] set is reported as soon as it is read, along with the leaf
+/// whose path reads it. Other corruptions only show at the root.
+pub(crate) fn compute_merkle_root(
+    queue: &[(U256, U256)],
+    proof: &[U256],
+    expected_root: U256,
+) -> Result<usize, MerkleVerificationError> {
+    walk_merkle_tree(queue, proof, expected_root, &mut HashMap::new())
+}
+
+/// [compute_merkle_root], also recording in `nodes` the hash of every node and sibling visited.
+fn walk_merkle_tree(
+    queue: &[(U256, U256)],
+    proof: &[U256],
+    expected_root: U256,
+    nodes: &mut HashMap<U256, U256>,
+) -> Result<usize, MerkleVerificationError> {
+    // Each node carries the first leaf of the queue below it.
+    let mut queue: VecDeque<(U256, U256, U256)> = queue
+        .iter()
+        .map(|&(index, hash)| (index, hash, index))
+        .collect();
+    let leaf_layer = queue.front().map_or(0, |&(index, _, _)| index.bits());
+    let mut proof_iter = proof.iter();
+    let mut n_consumed = 0;
+
+    loop {
+        // An empty queue has nothing to authenticate against the root.
+        let (index, hash, leaf) =
+            queue
+                .pop_front()
+                .unwrap_or((U256::one(), U256::zero(), U256::one()));
+        if index <= U256::one() {
+            if hash != expected_root {
+                return Err(MerkleVerificationError::RootMismatch {
+                    expected: expected_root,
+                    computed: hash,
+                });
+            }
+            return Ok(n_consumed);
+        }
+
+        let sibling_index = index ^ U256::one();
+        let sibling_hash = match queue.front() {
+            Some(&(next_index, next_hash, _)) if next_index == sibling_index => {
+                queue.pop_front();
+                next_hash
+            }
+            _ => {
+                n_consumed += 1;
+                let sibling_hash = *proof_iter.next().ok_or(
+                    MerkleVerificationError::MissingAuthenticationNode {
+                        node: sibling_index,
+                    },
+                )?;
+                if sibling_index.bits() < leaf_layer
+                    && sibling_hash & !commitment_mask() != 0.into()
+                {
+                    return Err(MerkleVerificationError::NodeMismatch {
+                        node: sibling_index,
+                        leaf,
+                        hash: sibling_hash,
+                    });
+                }
+                sibling_hash
+            }
+        };
+
+        nodes.insert(index, hash);
+        nodes.insert(sibling_index, sibling_hash);
+
+        let parent_hash = if index.bit(0) {
+            merkle_hash(sibling_hash, hash)
+        } else {
+            merkle_hash(hash, sibling_hash)
+        };
+        queue.push_back((index >> 1, parent_hash, leaf));
+    }
+}
+
+/// Authentication nodes, in the order `MerkleVerifier.verifyMerkle` reads them, for the queue of
+/// `(node index, hash)` pairs, taken from the `nodes` visited when verifying a larger queue.
+fn authentication_nodes(
+    queue: &[(U256, U256)],
+    nodes: &HashMap<U256, U256>,
+) -> Result<Vec<U256>, MerkleVerificationError> {
+    let mut queue: VecDeque<U256> = queue.iter().map(|&(index, _)| index).collect();
+    let mut proof = vec![];
+
+    while let Some(index) = queue.pop_front() {
+        if index <= U256::one() {
+            break;
+        }
+        let sibling_index = index ^ U256::one();
+        if queue.front() == Some(&sibling_index) {
+            queue.pop_front();
+        } else {
+            let sibling_hash = nodes.get(&sibling_index).ok_or(
+                MerkleVerificationError::MissingAuthenticationNode {
+                    node: sibling_index,
+                },
+            )?;
+            proof.push(*sibling_hash);
+        }
+        queue.push_back(index >> 1);
     }
+    Ok(proof)
 }
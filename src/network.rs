@@ -0,0 +1,98 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{AdapterError, ParseError},
+    submission::ContractAddresses,
+};
+
+/// Names of the built-in network profiles, see [NetworkConfig::builtin].
+pub const BUILTIN_NETWORKS: &[&str] = &["mainnet"];
+
+/// A deployment of the verifier contracts the split proofs are submitted to.
+///
+/// Besides the built-in profiles, a config can be loaded from a TOML or JSON file for teams
+/// that deploy their own verifier stack:
+///
+/// ```toml
+/// name = "my-stack"
+/// chain_id = 11155111
+///
+/// [contracts]
+/// merkle_statement = "0x..."
+/// fri_statement = "0x..."
+/// memory_page_fact_registry = "0x..."
+/// gps_statement_verifier = "0x..."
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NetworkConfig {
+    pub name: String,
+    pub chain_id: u64,
+    pub contracts: ContractAddresses,
+}
+
+impl NetworkConfig {
+    /// The Ethereum mainnet deployment verifying with the previous version of the
+    /// `GpsStatementVerifier`, [`0xd51a3d50d4d2f99a345a66971e650eea064dd8df`](https://etherscan.io/address/0xd51a3d50d4d2f99a345a66971e650eea064dd8df).
+    pub fn mainnet() -> NetworkConfig {
+        NetworkConfig {
+            name: "mainnet".to_string(),
+            chain_id: 1,
+            contracts: ContractAddresses {
+                merkle_statement: address("0x634dcf4f1421fc4d95a968a559a450ad0245804c"),
+                fri_statement: address("0xdef8a3b280a54ee7ed4f72e1c7d6098ad8df44fb"),
+                memory_page_fact_registry: address("0x40864568f679c10ac9e72211500096a5130770fa"),
+                gps_statement_verifier: address("0xd51a3d50d4d2f99a345a66971e650eea064dd8df"),
+            },
+        }
+    }
+
+    /// The built-in profile called `name`, one of [BUILTIN_NETWORKS].
+    pub fn builtin(name: &str) -> Option<NetworkConfig> {
+        match name {
+            "mainnet" => Some(NetworkConfig::mainnet()),
+            _ => None,
+        }
+    }
+
+    /// Reads a config file, as TOML if its extension is `.toml` and as JSON otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<NetworkConfig, AdapterError> {
+        let path = path.as_ref();
+        let file = std::fs::read_to_string(path).map_err(|source| AdapterError::File {
+            path: path.display().to_string(),
+            source,
+        })?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            Ok(toml::from_str(&file).map_err(ParseError::from)?)
+        } else {
+            Ok(serde_json::from_str(&file).map_err(ParseError::from)?)
+        }
+    }
+}
+
+impl FromStr for NetworkConfig {
+    type Err = AdapterError;
+
+    /// Parses the name of a built-in profile.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        NetworkConfig::builtin(name).ok_or_else(|| AdapterError::UnknownNetwork {
+            name: name.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for NetworkConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (chain id {})", self.name, self.chain_id)
+    }
+}
+
+/// Parses an address literal of a built-in profile.
+fn address(hex: &str) -> Address {
+    Address::from_str(hex).expect("built-in addresses are valid")
+}
@@ -1,24 +1,26 @@
 use std::{collections::HashMap, sync::Arc};
 
 use ethers::{
-    abi::Token,
     contract::abigen,
-    core::k256::ecdsa::SigningKey,
-    middleware::SignerMiddleware,
-    providers::{Http, Provider},
-    signers::Wallet,
-    types::{Address, U256},
-    utils::{hex, keccak256},
+    providers::Middleware,
+    types::{Address, H256, U256},
 };
-use num_bigint::BigInt;
-use num_traits::{Num, One};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    annotated_proof::{MemorySegment, ProofParameters, PublicInput, PublicMemory},
-    default_prime, ContractFunctionCall,
+    annotated_proof::{ProofParameters, PublicInput, PublicMemory},
+    default_prime,
+    errors::{AdapterError, PublicInputError, TopologyError},
+    field::Felt252,
+    keccak_words, ContractFunctionCall,
 };
 
+/// Page type tag hashed into the facts of continuous memory pages by `MemoryPageFactRegistry`.
+const CONTINUOUS_PAGE: u64 = 1;
+
+/// Number of values of the bootloader config at the start of the bootloader output.
+const BOOTLOADER_CONFIG_SIZE: usize = 2;
+
 /// Proof for consistency check for out of domain sampling
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MainProof {
@@ -29,12 +31,51 @@ pub struct MainProof {
     pub interaction_alpha: U256,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct FactTopology {
     tree_structure: Vec<u8>,
     page_sizes: Vec<usize>,
 }
 
+impl FactTopology {
+    pub fn new(tree_structure: Vec<u8>, page_sizes: Vec<usize>) -> FactTopology {
+        FactTopology {
+            tree_structure,
+            page_sizes,
+        }
+    }
+
+    /// Pairs of (number of pages, number of nodes) describing how the pages are merged into the
+    /// output root.
+    pub fn tree_structure(&self) -> &[u8] {
+        &self.tree_structure
+    }
+
+    /// Sizes of the pages the task output is split into, in order.
+    pub fn page_sizes(&self) -> &[usize] {
+        &self.page_sizes
+    }
+}
+
+/// Result of [MainProof::generate_tasks_metadata].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TasksMetadata {
+    /// The `task_metadata` argument of `verifyProofAndRegister`.
+    pub task_metadata: Vec<U256>,
+    /// Fact registered by `GpsStatementVerifier` for each task, see [program_fact].
+    pub facts: Vec<H256>,
+    /// Hash of the root of the output merkle tree of each task.
+    pub output_roots: Vec<U256>,
+    /// Sizes of the pages of all tasks, in the order they are hashed into the output roots.
+    pub page_sizes: Vec<usize>,
+}
+
+/// Fact registered by `GpsStatementVerifier` for a task: keccak256(program_hash, output_root),
+/// which can be checked with `isValid` on the fact registry once the main proof is verified.
+pub fn program_fact(program_hash: U256, output_root: U256) -> H256 {
+    keccak_words(&[program_hash, output_root])
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FactNode {
     node_hash: U256,
@@ -70,7 +111,6 @@ abigen!(
     derives(serde::Deserialize, serde::Serialize)
 );
 
-// todo use thiserror
 impl MainProof {
     pub fn new(
         proof: Vec<U256>,
@@ -120,242 +160,225 @@ impl MainProof {
     }
 
     /// Collect and serialize cairo public input
-    fn cairo_aux_input(&self) -> Vec<U256> {
-        let log_n_steps = (self.public_input.n_steps as f64).log2() as u64;
-        let mut cairo_aux_input = vec![
-            U256::from(log_n_steps),
-            U256::from(self.public_input.rc_min),
-            U256::from(self.public_input.rc_max),
-        ];
-
-        // Encoding the 'layout' string to its ASCII byte representation and converting to U256
-        let layout_big = U256::from_big_endian(self.public_input.layout.as_bytes());
-        cairo_aux_input.push(layout_big);
-
-        // Extend with serialized segments
-        let serialized_segments = self.serialize_segments();
-        cairo_aux_input.extend(serialized_segments);
-
+    fn cairo_aux_input(&self) -> Result<Vec<U256>, PublicInputError> {
         let z = self.interaction_z;
         let alpha = self.interaction_alpha;
 
-        let memory_pages_public_input =
-            self.memory_page_public_input(self.public_input.public_memory.clone(), z, alpha);
+        let mut cairo_aux_input = Self::public_input_words(&self.public_input)?;
 
-        // Extend with memory pages public input - assuming this is already a Vec<U256>
-        cairo_aux_input.extend(memory_pages_public_input);
-
-        // Append z and alpha
+        // Append the products of the pages, then z and alpha
+        cairo_aux_input.extend(Self::page_products(
+            &self.public_input.public_memory,
+            z,
+            alpha,
+        )?);
         cairo_aux_input.push(z);
         cairo_aux_input.push(alpha);
 
-        cairo_aux_input
+        Ok(cairo_aux_input)
     }
 
-    /// Serialize memory segments in order
-    fn serialize_segments(&self) -> Vec<U256> {
-        let segment_names = [
-            "program",
-            "execution",
-            "output",
-            "pedersen",
-            "range_check",
-            "ecdsa",
-            "bitwise",
-            "ec_op",
-            "keccak",
-            "poseidon",
+    /// Serialize the part of the cairo public input known before the interaction: everything but
+    /// the page products and the interaction elements. Its hash seeds the verifier channel.
+    pub(crate) fn public_input_words(
+        public_input: &PublicInput,
+    ) -> Result<Vec<U256>, PublicInputError> {
+        let log_n_steps = (public_input.n_steps as f64).log2() as u64;
+        let mut words = vec![
+            U256::from(log_n_steps),
+            U256::from(public_input.rc_min),
+            U256::from(public_input.rc_max),
         ];
 
-        let segments = &self.public_input.memory_segments;
-        let mut sorted_segments: Vec<MemorySegment> = Vec::new();
+        // Encoding the 'layout' string to its ASCII byte representation and converting to U256
+        let layout_big = U256::from_big_endian(public_input.layout.as_bytes());
+        words.push(layout_big);
 
-        for name in segment_names.iter() {
-            let segment: Option<&MemorySegment> = segments.get(*name);
-            if let Some(seg) = segment {
-                sorted_segments.push(seg.clone());
-            }
-        }
+        // Extend with serialized segments
+        words.extend(Self::serialize_segments(public_input)?);
+
+        // Extend with memory pages public input
+        words.extend(Self::memory_page_public_input(&public_input.public_memory)?);
 
-        assert_eq!(sorted_segments.len(), segments.len());
+        Ok(words)
+    }
+
+    /// Serialize memory segments in the order of the layout
+    fn serialize_segments(public_input: &PublicInput) -> Result<Vec<U256>, PublicInputError> {
+        let layout = public_input.layout()?;
+        let segment_names = layout.segment_names();
+        let segments = &public_input.memory_segments;
+
+        if let Some(name) = segments
+            .keys()
+            .find(|name| !segment_names.contains(&name.as_str()))
+        {
+            return Err(PublicInputError::UnknownSegment { name: name.clone() });
+        }
 
         let mut result: Vec<U256> = Vec::new();
-        for segment in sorted_segments {
+        for name in segment_names {
+            let segment = segments
+                .get(*name)
+                .ok_or_else(|| PublicInputError::MissingSegment {
+                    layout,
+                    name: name.to_string(),
+                })?;
             result.push(U256::from(segment.begin_addr));
             result.push(U256::from(segment.stop_ptr));
         }
 
-        result
+        Ok(result)
     }
 
     /// Calculate accumulated product for continuous memory
     fn calculate_product(
-        prod: U256,
-        z: U256,
-        alpha: U256,
-        memory_address: U256,
-        memory_value: U256,
-        prime: U256,
-    ) -> U256 {
-        let bigint_prod = BigInt::from_str_radix(&prod.to_string(), 10).unwrap();
-        let bigint_alpha = BigInt::from_str_radix(&alpha.to_string(), 10).unwrap();
-        let bigint_z = BigInt::from_str_radix(&z.to_string(), 10).unwrap();
-        let bigint_memory_value = BigInt::from_str_radix(&memory_value.to_string(), 10).unwrap();
-        let bigint_memory_address =
-            BigInt::from_str_radix(&memory_address.to_string(), 10).unwrap();
-        let bigint_prime = BigInt::from_str_radix(&prime.to_string(), 10).unwrap();
-
-        let multiply =
-            bigint_prod * (bigint_z - (bigint_memory_address + bigint_alpha * bigint_memory_value));
-        let mod_multiply = multiply.modpow(&BigInt::one(), &bigint_prime);
-        U256::from_dec_str(&mod_multiply.to_string()).unwrap()
+        prod: Felt252,
+        z: Felt252,
+        alpha: Felt252,
+        memory_address: Felt252,
+        memory_value: Felt252,
+    ) -> Felt252 {
+        prod * (z - (memory_address + alpha * memory_value))
     }
 
-    /// Calculate accomulative product for each memory page
-    fn get_pages_and_products(
-        &self,
-        public_memory: Vec<PublicMemory>,
-        z: U256,
-        alpha: U256,
-    ) -> (HashMap<u32, Vec<U256>>, HashMap<u32, U256>) {
+    /// Group public memory into pages of interleaved addresses and values, by page index
+    fn get_pages(public_memory: &[PublicMemory]) -> HashMap<u32, Vec<U256>> {
         let mut pages: HashMap<u32, Vec<U256>> = HashMap::new();
-        let mut page_prods: HashMap<u32, U256> = HashMap::new();
 
         for cell in public_memory {
             let page = pages.entry(cell.page).or_default();
-            let memory_address = U256::from(cell.address);
-            let memory_value = U256::from_str_radix(&cell.value, 16).unwrap();
-            page.push(memory_address);
-            page.push(memory_value);
-
-            let prod = page_prods.entry(cell.page).or_insert(U256::one());
-
-            *prod = Self::calculate_product(
-                *prod,
-                z,
-                alpha,
-                memory_address,
-                memory_value,
-                default_prime(),
-            );
+            page.push(U256::from(cell.address));
+            page.push(cell.value);
         }
 
-        (pages, page_prods)
+        pages
     }
 
-    /// Construct contract args for public input of memory pages
-    fn memory_page_public_input(
-        &self,
-        public_memory: Vec<PublicMemory>,
+    /// Calculate accomulative product for each memory page, ordered by page index
+    fn page_products(
+        public_memory: &[PublicMemory],
         z: U256,
         alpha: U256,
-    ) -> Vec<U256> {
+    ) -> Result<Vec<U256>, PublicInputError> {
+        let pages = Self::get_pages(public_memory);
+
+        (0..pages.len() as u32)
+            .map(|i| {
+                let page = pages
+                    .get(&i)
+                    .ok_or(PublicInputError::MissingPage { page: i })?;
+                let prod = page.chunks(2).fold(Felt252::ONE, |prod, cell| {
+                    Self::calculate_product(
+                        prod,
+                        z.into(),
+                        alpha.into(),
+                        cell[0].into(),
+                        cell[1].into(),
+                    )
+                });
+                Ok(prod.into())
+            })
+            .collect()
+    }
+
+    /// Construct contract args for public input of memory pages, without the page products
+    fn memory_page_public_input(
+        public_memory: &[PublicMemory],
+    ) -> Result<Vec<U256>, PublicInputError> {
         let mut result: Vec<U256> = Vec::new();
 
-        // Get pages and page_prods
-        let (pages, page_prods) = self.get_pages_and_products(public_memory.clone(), z, alpha);
+        let pages = Self::get_pages(public_memory);
 
         // Append padding values for public memory
-        let padding_cell = &public_memory[0];
-        let memory_address = U256::from(padding_cell.address);
-        let memory_value = U256::from_str_radix(&padding_cell.value, 16).unwrap();
-        result.push(memory_address);
-        result.push(memory_value);
+        let padding_cell = public_memory
+            .first()
+            .ok_or(PublicInputError::EmptyPublicMemory)?;
+        result.push(U256::from(padding_cell.address));
+        result.push(padding_cell.value);
 
         result.push(U256::from(pages.len()));
 
-        for i in 0..pages.len() {
-            let page = pages.get(&(i as u32)).unwrap();
+        for i in 0..pages.len() as u32 {
+            let page = pages
+                .get(&i)
+                .ok_or(PublicInputError::MissingPage { page: i })?;
             let page_hash = if i == 0 {
-                let tokens: Vec<Token> = page.iter().map(|val| Token::Uint(*val)).collect();
-                let encoded = ethers::abi::encode_packed(&[Token::Array(tokens)]).unwrap();
-                U256::from(keccak256(encoded.as_slice()).as_slice())
+                keccak_words(page)
             } else {
                 // Verify that the addresses of the page are indeed continuous
-                let range: Vec<U256> = (0..page.len() as u64 / 2)
-                    .map(|i| page[0] + U256::from(i))
-                    .collect();
-                assert!(page.iter().step_by(2).eq(range.iter()));
-                result.push(page[0]); // First address
-
-                let tokens: Vec<Token> = page
+                let start_address = page[0];
+                if let Some(address) = page
                     .iter()
-                    .skip(1)
                     .step_by(2)
-                    .map(|val| Token::Uint(*val))
-                    .collect();
-                let encoded = ethers::abi::encode_packed(&[Token::Array(tokens)]).unwrap();
-                U256::from(keccak256(encoded.as_slice()).as_slice())
+                    .enumerate()
+                    .find(|(offset, address)| **address != start_address + U256::from(*offset))
+                    .map(|(_, address)| *address)
+                {
+                    return Err(PublicInputError::NonContinuousPage { page: i, address });
+                }
+                result.push(start_address); // First address
+
+                let values: Vec<U256> = page.iter().skip(1).step_by(2).cloned().collect();
+                keccak_words(&values)
             };
 
             result.push(U256::from(page.len() as u64 / 2)); // Page size
-            result.push(page_hash); // Page hash
+            result.push(U256::from_big_endian(page_hash.as_bytes())); // Page hash
         }
 
-        // Append the products of the pages
-        // Note: this assumes that the pages are ordered from 0 to n
-        for index in 0..page_prods.len() {
-            let page_prod = page_prods.get(&(index as u32)).unwrap();
-            result.push(*page_prod);
-        }
-
-        result
+        Ok(result)
     }
 
-    pub fn memory_page_registration_args(&self) -> (RegularMemoryPage, Vec<ContinuousMemoryPage>) {
-        let (pages, _) = self.get_pages_and_products(
-            self.public_input.public_memory.clone(),
-            self.interaction_z,
-            self.interaction_alpha,
-        );
+    pub fn memory_page_registration_args(
+        &self,
+    ) -> Result<(RegularMemoryPage, Vec<ContinuousMemoryPage>), PublicInputError> {
+        let pages = Self::get_pages(&self.public_input.public_memory);
+        let page = |i: u32| {
+            pages
+                .get(&i)
+                .ok_or(PublicInputError::MissingPage { page: i })
+        };
 
         let regular_page = RegularMemoryPage {
-            page: pages.get(&0).unwrap().clone(),
+            page: page(0)?.clone(),
         };
 
-        let continuous_pages: Vec<ContinuousMemoryPage> = (1..pages.len() as u32)
-            .map(|i| ContinuousMemoryPage {
-                start_address: pages.get(&i).unwrap()[0],
-                values: pages
-                    .get(&i)
-                    .unwrap()
-                    .iter()
-                    .skip(1)
-                    .step_by(2)
-                    .cloned()
-                    .collect(),
+        let continuous_pages = (1..pages.len() as u32)
+            .map(|i| {
+                let page = page(i)?;
+                Ok(ContinuousMemoryPage {
+                    start_address: page[0],
+                    values: page.iter().skip(1).step_by(2).cloned().collect(),
+                })
             })
-            .collect();
+            .collect::<Result<Vec<ContinuousMemoryPage>, PublicInputError>>()?;
 
-        (regular_page, continuous_pages)
+        Ok((regular_page, continuous_pages))
     }
 
-    //todo use thiserror
-    fn extract_public_memory(public_input: &PublicInput) -> Result<HashMap<u32, U256>, String> {
+    fn extract_public_memory(
+        public_input: &PublicInput,
+    ) -> Result<HashMap<u32, U256>, PublicInputError> {
         let mut memory_map = HashMap::new();
         for entry in &public_input.public_memory {
             let addr = entry.address;
-            let value = &entry.value;
             if memory_map.contains_key(&addr) {
-                return Err(format!(
-                    "Duplicate public memory entries found with the same address: {}",
-                    addr
-                ));
+                return Err(PublicInputError::DuplicateAddress { address: addr });
             }
-            memory_map.insert(addr, U256::from_str_radix(value, 16).unwrap());
+            memory_map.insert(addr, entry.value);
         }
         Ok(memory_map)
     }
 
-    //todo use thiserror
     fn extract_program_output(
         public_input: &PublicInput,
         memory: &HashMap<u32, U256>,
-    ) -> Result<Vec<U256>, String> {
+    ) -> Result<Vec<U256>, PublicInputError> {
         let output_segment = public_input
             .memory_segments
             .get("output")
-            .ok_or("Missing output segment.")?;
+            .ok_or(PublicInputError::MissingOutputSegment)?;
 
         let stop_ptr = output_segment.stop_ptr;
 
@@ -363,55 +386,141 @@ impl MainProof {
         for addr in output_segment.begin_addr..stop_ptr {
             let value = *memory
                 .get(&addr)
-                .ok_or(format!("Missing value for address: {}", addr))?;
+                .ok_or(PublicInputError::MissingMemoryValue { address: addr })?;
             output.push(value);
         }
         Ok(output)
     }
 
-    #[allow(dead_code)]
-    fn get_trivial_topology(public_memory: &Vec<PublicMemory>) -> Vec<FactTopology> {
-        let mut page_sizes = HashMap::new();
+    /// Page id of every public memory cell of the output segment, in address order.
+    fn extract_output_pages(public_input: &PublicInput) -> Result<Vec<u32>, PublicInputError> {
+        let output_segment = public_input
+            .memory_segments
+            .get("output")
+            .ok_or(PublicInputError::MissingOutputSegment)?;
 
-        for item in public_memory {
-            *page_sizes.entry(item.page).or_insert(0) += 1;
+        let pages: HashMap<u32, u32> = public_input
+            .public_memory
+            .iter()
+            .map(|cell| (cell.address, cell.page))
+            .collect();
+
+        (output_segment.begin_addr..output_segment.stop_ptr)
+            .map(|addr| {
+                pages
+                    .get(&addr)
+                    .copied()
+                    .ok_or(PublicInputError::MissingMemoryValue { address: addr })
+            })
+            .collect()
+    }
+
+    /// Topology of a task whose output is not split into pages: a single page holding all of it.
+    fn get_trivial_topology(output_size: usize) -> FactTopology {
+        FactTopology {
+            tree_structure: vec![1, 0],
+            page_sizes: vec![output_size],
         }
+    }
 
-        // Ignore the main page (page 0)
-        page_sizes.remove(&0);
+    /// See [PublicInput::fact_topologies].
+    pub(crate) fn fact_topologies(
+        public_input: &PublicInput,
+        include_bootloader_config: bool,
+        tree_structures: &HashMap<usize, Vec<u8>>,
+    ) -> Result<Vec<FactTopology>, AdapterError> {
+        let output = Self::tasks_output(public_input, include_bootloader_config)?;
+        let mut pages = Self::extract_output_pages(public_input)?;
+        if include_bootloader_config {
+            pages.drain(..BOOTLOADER_CONFIG_SIZE);
+        }
+
+        let n_tasks = Self::output_usize(&output, 0)?;
+        let mut ptr = 1;
+        let mut next_page = 1;
+        let mut topologies = Vec::with_capacity(n_tasks.min(output.len()));
 
-        let mut topologies = Vec::new();
-        for (i, _) in page_sizes.iter().enumerate() {
-            let page_size = page_sizes.get(&(i as u32 + 1)).cloned().unwrap_or(0);
-            topologies.push(FactTopology {
-                tree_structure: vec![1, 0],
-                page_sizes: vec![page_size],
-            });
+        for task in 0..n_tasks {
+            if ptr + 1 >= output.len() {
+                return Err(TopologyError::TaskOutOfBounds { task }.into());
+            }
+            let task_output_len = Self::output_usize(&output, ptr)?;
+            if task_output_len < 2 {
+                return Err(TopologyError::InvalidOutputValue {
+                    offset: ptr,
+                    value: output[ptr],
+                }
+                .into());
+            }
+            let end = ptr.saturating_add(task_output_len);
+            if end > output.len() {
+                return Err(TopologyError::TaskOutputTooLarge { task }.into());
+            }
+
+            // Split the task output, without its size and program hash, into runs of the same page
+            let mut page_runs: Vec<(u32, usize)> = Vec::new();
+            for &page in &pages[ptr + 2..end] {
+                match page_runs.last_mut() {
+                    Some((last, size)) if *last == page => *size += 1,
+                    _ => page_runs.push((page, 1)),
+                }
+            }
+
+            let topology = if page_runs.iter().all(|&(page, _)| page == 0) {
+                Self::get_trivial_topology(task_output_len - 2)
+            } else {
+                // Like cairo-lang's get_page_sizes_from_page_dict, a leading run in page 0 is
+                // the first page of a task whose output starts in the main page.
+                let first_page_run = usize::from(page_runs[0].0 == 0);
+                for &(page, _) in &page_runs[first_page_run..] {
+                    if page != next_page {
+                        return Err(TopologyError::UnexpectedPage {
+                            task,
+                            page,
+                            expected: next_page,
+                        }
+                        .into());
+                    }
+                    next_page += 1;
+                }
+                let page_sizes: Vec<usize> = page_runs.iter().map(|&(_, size)| size).collect();
+                let tree_structure = match (tree_structures.get(&task), page_sizes.len()) {
+                    (Some(tree_structure), _) => tree_structure.clone(),
+                    (None, 1) => vec![1, 0],
+                    (None, n_pages) => {
+                        return Err(TopologyError::MissingTreeStructure { task, n_pages }.into())
+                    }
+                };
+                FactTopology {
+                    tree_structure,
+                    page_sizes,
+                }
+            };
+
+            topologies.push(topology);
+            ptr = end;
         }
 
-        topologies
-    }
+        if ptr != output.len() {
+            return Err(TopologyError::UnprocessedOutput {
+                processed: ptr,
+                len: output.len(),
+            }
+            .into());
+        }
 
-    fn keccak_ints(&self, values: &[U256]) -> Result<String, String> {
-        let values_bytes = values
-            .iter()
-            .flat_map(|&value| {
-                let mut bytes = [0u8; 32]; // U256 is 32 bytes
-                value.to_big_endian(&mut bytes);
-                bytes
-            })
-            .collect::<Vec<u8>>();
+        Ok(topologies)
+    }
 
-        let result = keccak256(values_bytes.as_slice());
-        // convert result to hex string
-        Ok(hex::encode(result))
+    fn keccak_ints(&self, values: &[U256]) -> U256 {
+        U256::from_big_endian(keccak_words(values).as_bytes())
     }
 
     fn generate_output_root(
         &self,
         program_output: &[U256],
         fact_topology: &FactTopology,
-    ) -> Result<FactNode, String> {
+    ) -> Result<FactNode, TopologyError> {
         let mut page_sizes = fact_topology.page_sizes.clone();
         let tree_structure = &fact_topology.tree_structure;
         let mut offset = 0;
@@ -420,16 +529,21 @@ impl MainProof {
         let mut tree_iter = tree_structure.iter();
         while let (Some(&n_pages), Some(&n_nodes)) = (tree_iter.next(), tree_iter.next()) {
             if n_pages as usize > page_sizes.len() {
-                return Err("Invalid tree structure: n_pages is out of range.".to_string());
+                return Err(TopologyError::InvalidTreeStructure(
+                    "n_pages is out of range",
+                ));
             }
 
             for _ in 0..n_pages {
                 let page_size = page_sizes.remove(0);
-                let page_hash = self.keccak_ints(&program_output[offset..offset + page_size])?;
+                let page = program_output.get(offset..offset + page_size).ok_or(
+                    TopologyError::InvalidTreeStructure("page sizes exceed the program output"),
+                )?;
+                let page_hash = self.keccak_ints(page);
 
                 offset += page_size;
                 node_stack.push(FactNode {
-                    node_hash: U256::from_str_radix(&page_hash, 16).unwrap(),
+                    node_hash: page_hash,
                     end_offset: offset,
                     size: page_size,
                     children: Vec::new(),
@@ -437,7 +551,9 @@ impl MainProof {
             }
 
             if n_nodes as usize > node_stack.len() {
-                return Err("Invalid tree structure: n_nodes is out of range.".to_string());
+                return Err(TopologyError::InvalidTreeStructure(
+                    "n_nodes is out of range",
+                ));
             }
 
             if n_nodes > 0 {
@@ -447,10 +563,9 @@ impl MainProof {
                     .flat_map(|node| vec![node.node_hash, U256::from(node.end_offset)])
                     .collect();
 
-                let data_hash = self.keccak_ints(&node_data)?;
-                let node_hash = U256::one() + U256::from_str_radix(&data_hash, 16).unwrap();
+                let node_hash = U256::one() + self.keccak_ints(&node_data);
 
-                let end_offset = child_nodes.last().unwrap().end_offset;
+                let end_offset = child_nodes.last().map_or(0, |node| node.end_offset);
                 let size = child_nodes.iter().map(|node| node.size).sum();
 
                 node_stack.push(FactNode {
@@ -462,70 +577,97 @@ impl MainProof {
             }
         }
 
-        if node_stack.len() != 1 {
-            return Err("Invalid tree structure: stack contains more than one node.".to_string());
-        }
+        let root = match (node_stack.pop(), node_stack.is_empty()) {
+            (Some(root), true) => root,
+            _ => {
+                return Err(TopologyError::InvalidTreeStructure(
+                    "stack does not contain exactly one node",
+                ))
+            }
+        };
         if !page_sizes.is_empty() {
-            return Err("Invalid tree structure: not all pages were processed.".to_string());
+            return Err(TopologyError::InvalidTreeStructure(
+                "not all pages were processed",
+            ));
         }
-        if offset != node_stack[0].end_offset || offset != program_output.len() {
-            return Err("Invalid tree structure: offset mismatch.".to_string());
+        if offset != root.end_offset || offset != program_output.len() {
+            return Err(TopologyError::InvalidTreeStructure("offset mismatch"));
         }
 
-        Ok(node_stack.pop().unwrap())
+        Ok(root)
     }
 
+    /// Returns the program fact of a task together with the hash of its output root.
     fn generate_program_fact(
         &self,
         program_hash: U256,
         program_output: Vec<U256>,
         fact_topology: &FactTopology,
-    ) -> Result<String, String> {
+    ) -> Result<(H256, U256), TopologyError> {
         let output_root_node = self.generate_output_root(&program_output, fact_topology)?;
-        let hash = self.keccak_ints(&[program_hash, output_root_node.node_hash])?;
-        Ok(hash)
+        Ok((
+            program_fact(program_hash, output_root_node.node_hash),
+            output_root_node.node_hash,
+        ))
     }
 
+    /// Reads the output value at `offset` as a size or count.
+    /// The bootloader output starting at the number of tasks, that is without the bootloader
+    /// config when `include_bootloader_config`. Offsets and lengths in [TopologyError] are
+    /// relative to it.
+    fn tasks_output(
+        public_input: &PublicInput,
+        include_bootloader_config: bool,
+    ) -> Result<Vec<U256>, AdapterError> {
+        let memory = Self::extract_public_memory(public_input)?;
+        let mut output = Self::extract_program_output(public_input, &memory)?;
+        if include_bootloader_config {
+            if output.len() <= BOOTLOADER_CONFIG_SIZE {
+                return Err(TopologyError::OutputTooShort { len: 0 }.into());
+            }
+            output.drain(..BOOTLOADER_CONFIG_SIZE);
+        }
+        Ok(output)
+    }
+
+    fn output_usize(output: &[U256], offset: usize) -> Result<usize, TopologyError> {
+        let value = *output
+            .get(offset)
+            .ok_or(TopologyError::OutputTooShort { len: output.len() })?;
+        if value > U256::from(usize::MAX) {
+            return Err(TopologyError::InvalidOutputValue { offset, value });
+        }
+        Ok(value.as_usize())
+    }
+
+    /// Serialize the `task_metadata` argument of `verifyProofAndRegister` from the bootloader
+    /// output, and compute the facts the verifier registers for each task.
     pub fn generate_tasks_metadata(
         &self,
         include_bootloader_config: bool,
         fact_topologies: Vec<FactTopology>,
-    ) -> Result<Vec<U256>, String> {
-        let bootloader_config_size = 2;
+    ) -> Result<TasksMetadata, AdapterError> {
         let program_output_header = 2;
-        let n_programs_entry = if include_bootloader_config {
-            bootloader_config_size
-        } else {
-            0
-        };
-        let memory = Self::extract_public_memory(&self.public_input)?;
-        let mut output = Self::extract_program_output(&self.public_input, &memory)?;
+        let output = Self::tasks_output(&self.public_input, include_bootloader_config)?;
 
-        let n_programs: usize = output
-            .get(n_programs_entry)
-            .ok_or("n_programs_entry index out of range")?
-            .as_usize();
-
-        if n_programs * program_output_header >= output.len() {
-            return Err("output_length is too short.".to_string());
-        }
-
-        if include_bootloader_config {
-            output = output[bootloader_config_size..].to_vec();
+        let n_programs = Self::output_usize(&output, 0)?;
+        if n_programs.saturating_mul(program_output_header) >= output.len() {
+            return Err(TopologyError::OutputTooShort { len: output.len() }.into());
         }
 
         let n_tasks = output[0];
         let mut task_metadata = vec![n_tasks];
         let mut facts = vec![];
-        let mut task_outputs = vec![];
-        let mut expected_page_sizes = vec![];
+        let mut output_roots = vec![];
+        let mut page_sizes = vec![];
         let mut ptr = 1;
 
-        for fact_topology in fact_topologies {
+        for (task, fact_topology) in fact_topologies.into_iter().enumerate() {
             if ptr + 1 >= output.len() {
-                return Err("Output index out of bounds.".to_string());
+                return Err(TopologyError::TaskOutOfBounds { task }.into());
             }
             let task_output_size = output[ptr];
+            let task_output_len = Self::output_usize(&output, ptr)?;
             let program_hash = output[ptr + 1];
             task_metadata.push(task_output_size);
             task_metadata.push(program_hash);
@@ -538,34 +680,46 @@ impl MainProof {
                     .collect::<Vec<U256>>(),
             );
 
-            let end = ptr + task_output_size.as_usize();
+            if task_output_len < program_output_header {
+                return Err(TopologyError::InvalidOutputValue {
+                    offset: ptr,
+                    value: task_output_size,
+                }
+                .into());
+            }
+            let end = ptr.saturating_add(task_output_len);
             if end > output.len() {
-                return Err("Task output size exceeds output length.".to_string());
+                return Err(TopologyError::TaskOutputTooLarge { task }.into());
             }
             let task_output = &output[ptr + 2..end];
-            task_outputs.push(task_output.to_vec());
 
-            let fact =
+            let (fact, output_root) =
                 self.generate_program_fact(program_hash, task_output.to_vec(), &fact_topology)?;
             facts.push(fact);
-            ptr += task_output_size.as_usize();
+            output_roots.push(output_root);
+            ptr = end;
 
-            if task_output_size.as_usize() != 2 + fact_topology.page_sizes.iter().sum::<usize>() {
-                return Err("Page sizes do not match the task output size.".to_string());
+            if task_output_len != 2 + fact_topology.page_sizes.iter().sum::<usize>() {
+                return Err(TopologyError::PageSizesMismatch { task }.into());
             }
 
-            expected_page_sizes.extend_from_slice(&fact_topology.page_sizes);
+            page_sizes.extend_from_slice(&fact_topology.page_sizes);
         }
 
         if ptr != output.len() {
-            return Err(format!(
-                "Not all of the bootloader output was processed: {} != {}",
-                ptr,
-                output.len()
-            ));
+            return Err(TopologyError::UnprocessedOutput {
+                processed: ptr,
+                len: output.len(),
+            }
+            .into());
         }
 
-        Ok(task_metadata)
+        Ok(TasksMetadata {
+            task_metadata,
+            facts,
+            output_roots,
+            page_sizes,
+        })
     }
 
     /// Construct `verifyProofAndRegister` contract call
@@ -582,44 +736,92 @@ impl MainProof {
         }
     }
 
+    /// Fact registered by `MemoryPageFactRegistry.registerContinuousMemoryPage` for `continuous_page`:
+    /// keccak256(CONTINUOUS_PAGE, prime, n_values, z, alpha, prod, keccak256(values), start_address).
+    pub fn continuous_memory_page_fact_hash(&self, continuous_page: &ContinuousMemoryPage) -> H256 {
+        let prime = default_prime();
+        let prod =
+            continuous_page
+                .values
+                .iter()
+                .enumerate()
+                .fold(Felt252::ONE, |prod, (i, value)| {
+                    Self::calculate_product(
+                        prod,
+                        self.interaction_z.into(),
+                        self.interaction_alpha.into(),
+                        (continuous_page.start_address + U256::from(i)).into(),
+                        (*value).into(),
+                    )
+                });
+        let memory_hash = keccak_words(&continuous_page.values);
+
+        keccak_words(&[
+            U256::from(CONTINUOUS_PAGE),
+            prime,
+            U256::from(continuous_page.values.len()),
+            self.interaction_z,
+            self.interaction_alpha,
+            prod.into(),
+            U256::from_big_endian(memory_hash.as_bytes()),
+            continuous_page.start_address,
+        ])
+    }
+
     /// Initiate `verifyProofAndRegister` contract call
-    pub fn register_continuous_memory_page(
+    pub fn register_continuous_memory_page<M: Middleware>(
         &self,
         address: Address,
-        signer: Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+        signer: Arc<M>,
         continuous_page: ContinuousMemoryPage,
-    ) -> ContractFunctionCall {
+    ) -> ContractFunctionCall<M> {
         let contract = MemoryPageFactRegistryContract::new(address, signer);
 
-        let function_call = self.register_continuous_memory_page_call(continuous_page);
-        contract
-            .method("registerContinuousMemoryPage", function_call)
-            .unwrap()
+        let call = self.register_continuous_memory_page_call(continuous_page);
+        contract.register_continuous_memory_page(
+            call.start_addr,
+            call.values,
+            call.z,
+            call.alpha,
+            call.prime,
+        )
     }
 
     /// Construct `verifyProofAndRegister` contract call
-    pub fn contract_function_call(&self, task_metadata: Vec<U256>) -> VerifyProofAndRegisterCall {
-        VerifyProofAndRegisterCall {
+    pub fn contract_function_call(
+        &self,
+        task_metadata: Vec<U256>,
+    ) -> Result<VerifyProofAndRegisterCall, PublicInputError> {
+        let layout = self.public_input.layout()?;
+        let cairo_verifier_id = layout
+            .cairo_verifier_id()
+            .ok_or(PublicInputError::NoCairoVerifier { layout })?;
+
+        Ok(VerifyProofAndRegisterCall {
             proof_params: self.proof_params(),
             proof: self.proof.clone(),
             task_metadata,
-            cairo_aux_input: self.cairo_aux_input(),
-            cairo_verifier_id: U256::from(6),
-        }
+            cairo_aux_input: self.cairo_aux_input()?,
+            cairo_verifier_id: U256::from(cairo_verifier_id),
+        })
     }
 
     /// Initiate `verifyProofAndRegister` contract call
-    pub fn verify(
+    pub fn verify<M: Middleware>(
         &self,
         address: Address,
-        signer: Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+        signer: Arc<M>,
         task_metadata: Vec<U256>,
-    ) -> ContractFunctionCall {
+    ) -> Result<ContractFunctionCall<M>, PublicInputError> {
         let contract = GpsStatementVerifierContract::new(address, signer);
 
-        let function_call = self.contract_function_call(task_metadata);
-        contract
-            .method("verifyProofAndRegister", function_call)
-            .unwrap()
+        let call = self.contract_function_call(task_metadata)?;
+        Ok(contract.verify_proof_and_register(
+            call.proof_params,
+            call.proof,
+            call.task_metadata,
+            call.cairo_aux_input,
+            call.cairo_verifier_id,
+        ))
     }
 }
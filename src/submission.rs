@@ -0,0 +1,494 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ethers::{
+    abi::AbiEncode,
+    contract::{abigen, ContractError},
+    providers::{Middleware, PendingTransaction},
+    types::{Address, Bytes, TransactionReceipt, H256, U256, U64},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    annotation_parser::SplitProofs,
+    errors::{AdapterError, SubmissionError},
+    merkle_statement::MAX_N_MERKLE_VERIFIER_QUERIES,
+    oods_statement::{ContinuousMemoryPage, FactTopology},
+    ContractFunctionCall,
+};
+
+/// Addresses of the verifier contracts the split proofs are submitted to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ContractAddresses {
+    pub merkle_statement: Address,
+    pub fri_statement: Address,
+    pub memory_page_fact_registry: Address,
+    pub gps_statement_verifier: Address,
+}
+
+abigen!(
+    FactRegistryContract,
+    r#"[
+        function isValid(bytes32 fact) external view returns (bool)
+    ]"#
+);
+
+/// A single transaction of the submission, in the order it is sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `verifyMerkle` for the named trace merkle statement.
+    MerkleStatement(String),
+    /// `verifyFRI` for the FRI statement at this layer.
+    FriStatement(usize),
+    /// `registerContinuousMemoryPage` for the continuous page at this index (page index - 1).
+    ContinuousMemoryPage(usize),
+    /// `verifyProofAndRegister` for the main proof.
+    MainProof,
+}
+
+impl Step {
+    /// Name under which the step is recorded in the state file.
+    pub fn name(&self) -> String {
+        match self {
+            Step::MerkleStatement(name) => name.clone(),
+            Step::FriStatement(layer) => format!("FRI statement {}", layer),
+            Step::ContinuousMemoryPage(index) => format!("Continuous memory page {}", index),
+            Step::MainProof => "Main proof".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepStatus {
+    /// The transaction was sent but its receipt was not seen yet.
+    Pending,
+    /// The transaction was mined successfully.
+    Confirmed,
+    /// The fact of the step was already registered on-chain, so no transaction was sent.
+    Skipped,
+    /// The transaction was dropped from the mempool or reverted, and the step is sent again.
+    Dropped,
+}
+
+/// Progress of a single step, as saved in the state file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StepRecord {
+    pub step: String,
+    /// Hash of the transaction of the step, `None` when it was skipped.
+    pub tx_hash: Option<H256>,
+    /// Facts registered by the step: the statement fact, or the fact of every task for the
+    /// main proof.
+    pub facts: Vec<H256>,
+    pub status: StepStatus,
+    /// Hashes of the earlier transactions of the step that were dropped or reverted, oldest
+    /// first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced: Vec<H256>,
+}
+
+/// Progress of a submission, saved after every transaction so that a crashed run can resume.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SubmissionState {
+    pub steps: Vec<StepRecord>,
+}
+
+impl SubmissionState {
+    /// Loads the state from `path`, or returns an empty state if the file does not exist.
+    pub fn load(path: &Path) -> Result<SubmissionState, SubmissionError> {
+        if !path.exists() {
+            return Ok(SubmissionState::default());
+        }
+        let file = std::fs::read_to_string(path).map_err(|source| SubmissionError::StateFile {
+            path: path.display().to_string(),
+            source,
+        })?;
+        serde_json::from_str(&file).map_err(|source| SubmissionError::StateFormat {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    /// Writes the state to `path`, going through a temporary file so a crash never leaves a
+    /// truncated state behind.
+    pub fn save(&self, path: &Path) -> Result<(), SubmissionError> {
+        let state_file_error = |source| SubmissionError::StateFile {
+            path: path.display().to_string(),
+            source,
+        };
+        let json =
+            serde_json::to_string_pretty(self).map_err(|source| SubmissionError::StateFormat {
+                path: path.display().to_string(),
+                source,
+            })?;
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, json).map_err(state_file_error)?;
+        std::fs::rename(&tmp_path, path).map_err(state_file_error)
+    }
+
+    /// Returns the record of `step`, if any.
+    pub fn get(&self, step: &str) -> Option<&StepRecord> {
+        self.steps.iter().find(|record| record.step == step)
+    }
+
+    fn upsert(&mut self, record: StepRecord) {
+        match self.steps.iter_mut().find(|r| r.step == record.step) {
+            Some(existing) => *existing = record,
+            None => self.steps.push(record),
+        }
+    }
+}
+
+/// Submits [SplitProofs] to the verifier contracts: the trace merkle statements, the FRI
+/// statements, the continuous memory pages and finally the main proof.
+/// Progress is saved to `state_file` after every transaction, and steps already confirmed there
+/// are not sent again. Steps whose facts are already registered on-chain are skipped, see
+/// [skip_registered](Pipeline::skip_registered).
+pub struct Pipeline {
+    pub split_proofs: SplitProofs,
+    pub fact_topologies: Vec<FactTopology>,
+    pub contracts: ContractAddresses,
+    pub state_file: PathBuf,
+    /// Whether the bootloader output starts with the bootloader config, see
+    /// [generate_tasks_metadata](crate::oods_statement::MainProof::generate_tasks_metadata).
+    pub include_bootloader_config: bool,
+    /// Whether to query `isValid` before sending each step, and skip the steps whose facts are
+    /// already registered, for example by an earlier submission sharing trace commitments.
+    pub skip_registered: bool,
+    /// Chain id of the network of `contracts`, such as
+    /// [NetworkConfig::chain_id](crate::network::NetworkConfig::chain_id). When set, the provider
+    /// must be on this chain, see [check_chain_id](Pipeline::check_chain_id).
+    pub chain_id: Option<u64>,
+    continuous_pages: Vec<ContinuousMemoryPage>,
+}
+
+impl Pipeline {
+    pub fn new(
+        split_proofs: SplitProofs,
+        fact_topologies: Vec<FactTopology>,
+        contracts: ContractAddresses,
+        state_file: impl Into<PathBuf>,
+    ) -> Result<Pipeline, AdapterError> {
+        // `GpsStatementVerifier` checks the fact of the whole queue of each trace commitment, so
+        // an oversized statement cannot be split into several `verifyMerkle` calls.
+        for (name, statement) in &split_proofs.merkle_statements {
+            if statement.n_queries() > MAX_N_MERKLE_VERIFIER_QUERIES {
+                return Err(SubmissionError::MerkleStatementTooLarge {
+                    name: name.clone(),
+                    n_queries: statement.n_queries(),
+                    max: MAX_N_MERKLE_VERIFIER_QUERIES,
+                }
+                .into());
+            }
+        }
+        let (_, continuous_pages) = split_proofs.main_proof.memory_page_registration_args()?;
+        Ok(Pipeline {
+            split_proofs,
+            fact_topologies,
+            contracts,
+            state_file: state_file.into(),
+            include_bootloader_config: true,
+            skip_registered: true,
+            chain_id: None,
+            continuous_pages,
+        })
+    }
+
+    /// The steps of the submission, in the order they are sent.
+    pub fn steps(&self) -> Vec<Step> {
+        let mut merkle_names: Vec<&String> = self.split_proofs.merkle_statements.keys().collect();
+        merkle_names.sort_by_key(|name| (name.len(), name.to_string()));
+
+        merkle_names
+            .into_iter()
+            .map(|name| Step::MerkleStatement(name.clone()))
+            .chain((0..self.split_proofs.fri_merkle_statements.len()).map(Step::FriStatement))
+            .chain((0..self.continuous_pages.len()).map(Step::ContinuousMemoryPage))
+            .chain(std::iter::once(Step::MainProof))
+            .collect()
+    }
+
+    /// Fails if [chain_id](Pipeline::chain_id) is set and `client` is connected to another chain,
+    /// so that nothing is sent to, or estimated against, contracts of the wrong network.
+    pub async fn check_chain_id<M: Middleware>(&self, client: &M) -> Result<(), AdapterError> {
+        let Some(expected) = self.chain_id else {
+            return Ok(());
+        };
+        let found = client
+            .get_chainid()
+            .await
+            .map_err(|e| SubmissionError::Provider {
+                step: "chain id".to_string(),
+                message: e.to_string(),
+            })?;
+        if found != U256::from(expected) {
+            return Err(SubmissionError::ChainIdMismatch { expected, found }.into());
+        }
+        Ok(())
+    }
+
+    /// Facts registered by `step`: the statement fact for the merkle, FRI and memory page steps,
+    /// and the fact of every task for the main proof.
+    pub fn facts(&self, step: &Step) -> Result<Vec<H256>, AdapterError> {
+        let fact = match step {
+            Step::MerkleStatement(name) => self.split_proofs.merkle_statements[name].fact_hash(),
+            Step::FriStatement(layer) => {
+                self.split_proofs.fri_merkle_statements[*layer].fact_hash()
+            }
+            Step::ContinuousMemoryPage(index) => self
+                .split_proofs
+                .main_proof
+                .continuous_memory_page_fact_hash(&self.continuous_pages[*index]),
+            Step::MainProof => {
+                return Ok(self
+                    .split_proofs
+                    .main_proof
+                    .generate_tasks_metadata(
+                        self.include_bootloader_config,
+                        self.fact_topologies.clone(),
+                    )?
+                    .facts)
+            }
+        };
+        Ok(vec![fact])
+    }
+
+    /// Address of the contract `step` is sent to.
+    pub fn target(&self, step: &Step) -> Address {
+        match step {
+            Step::MerkleStatement(_) => self.contracts.merkle_statement,
+            Step::FriStatement(_) => self.contracts.fri_statement,
+            Step::ContinuousMemoryPage(_) => self.contracts.memory_page_fact_registry,
+            Step::MainProof => self.contracts.gps_statement_verifier,
+        }
+    }
+
+    /// ABI-encoded calldata of `step`, starting with the function selector.
+    pub fn calldata(&self, step: &Step) -> Result<Bytes, AdapterError> {
+        let main_proof = &self.split_proofs.main_proof;
+        let calldata = match step {
+            Step::MerkleStatement(name) => self.split_proofs.merkle_statements[name]
+                .contract_function_call()
+                .encode(),
+            Step::FriStatement(layer) => self.split_proofs.fri_merkle_statements[*layer]
+                .contract_function_call()
+                .encode(),
+            Step::ContinuousMemoryPage(index) => main_proof
+                .register_continuous_memory_page_call(self.continuous_pages[*index].clone())
+                .encode(),
+            Step::MainProof => main_proof
+                .contract_function_call(self.task_metadata()?)?
+                .encode(),
+        };
+        Ok(calldata.into())
+    }
+
+    /// The `task_metadata` argument of the main proof.
+    fn task_metadata(&self) -> Result<Vec<U256>, AdapterError> {
+        Ok(self
+            .split_proofs
+            .main_proof
+            .generate_tasks_metadata(self.include_bootloader_config, self.fact_topologies.clone())?
+            .task_metadata)
+    }
+
+    /// Whether the [facts](Pipeline::facts) of `step` are already registered on-chain, in the
+    /// contract it is sent to.
+    pub async fn is_registered<M: Middleware>(
+        &self,
+        client: Arc<M>,
+        step: &Step,
+    ) -> Result<bool, AdapterError> {
+        let facts = self.facts(step)?;
+        if facts.is_empty() {
+            return Ok(false);
+        }
+
+        let contract = FactRegistryContract::new(self.target(step), client);
+        for fact in facts {
+            let is_valid = contract.is_valid(fact.into()).call().await.map_err(|e| {
+                SubmissionError::Contract {
+                    step: step.name(),
+                    message: decode_revert_message(e),
+                }
+            })?;
+            if !is_valid {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The steps of the submission, each with whether its facts are already registered and it
+    /// will be skipped by [run](Pipeline::run).
+    pub async fn preflight<M: Middleware>(
+        &self,
+        client: Arc<M>,
+    ) -> Result<Vec<(Step, bool)>, AdapterError> {
+        self.check_chain_id(&*client).await?;
+        let mut plan = Vec::new();
+        for step in self.steps() {
+            let registered = self.is_registered(client.clone(), &step).await?;
+            plan.push((step, registered));
+        }
+        Ok(plan)
+    }
+
+    /// Runs the submission, resuming from `state_file`, and returns the final state.
+    pub async fn run<M: Middleware>(
+        &self,
+        signer: Arc<M>,
+    ) -> Result<SubmissionState, AdapterError> {
+        self.check_chain_id(&*signer).await?;
+        let mut state = SubmissionState::load(&self.state_file)?;
+
+        for step in self.steps() {
+            let name = step.name();
+            let facts = self.facts(&step)?;
+
+            let mut replaced = vec![];
+            if let Some(record) = state.get(&name).cloned() {
+                if record.facts != facts {
+                    return Err(SubmissionError::StateMismatch {
+                        step: name,
+                        recorded: record.facts,
+                        expected: facts,
+                    }
+                    .into());
+                }
+                match (record.status, record.tx_hash) {
+                    (StepStatus::Confirmed | StepStatus::Skipped, _) => continue,
+                    // A crash may have happened after sending, wait for the transaction to be
+                    // mined or dropped before sending the step again.
+                    (StepStatus::Pending, Some(tx_hash)) => {
+                        let receipt = Self::wait_for_pending(&signer, &name, tx_hash).await?;
+                        let mined =
+                            receipt.is_some_and(|receipt| receipt.status == Some(U64::from(1)));
+                        let status = if mined {
+                            StepStatus::Confirmed
+                        } else {
+                            StepStatus::Dropped
+                        };
+                        state.upsert(StepRecord {
+                            status,
+                            ..record.clone()
+                        });
+                        state.save(&self.state_file)?;
+                        if mined {
+                            continue;
+                        }
+                    }
+                    (StepStatus::Pending | StepStatus::Dropped, _) => {}
+                }
+                replaced = record.replaced;
+                replaced.extend(record.tx_hash);
+            }
+
+            if self.skip_registered && self.is_registered(signer.clone(), &step).await? {
+                state.upsert(StepRecord {
+                    step: name,
+                    tx_hash: None,
+                    facts,
+                    status: StepStatus::Skipped,
+                    replaced,
+                });
+                state.save(&self.state_file)?;
+                continue;
+            }
+
+            let call = self.call(&step, signer.clone())?;
+            let pending_tx = call.send().await.map_err(|e| SubmissionError::Contract {
+                step: name.clone(),
+                message: decode_revert_message(e),
+            })?;
+            let tx_hash = pending_tx.tx_hash();
+
+            state.upsert(StepRecord {
+                step: name.clone(),
+                tx_hash: Some(tx_hash),
+                facts: facts.clone(),
+                status: StepStatus::Pending,
+                replaced: replaced.clone(),
+            });
+            state.save(&self.state_file)?;
+
+            let receipt = pending_tx
+                .await
+                .map_err(|e| SubmissionError::Provider {
+                    step: name.clone(),
+                    message: e.to_string(),
+                })?
+                .ok_or(SubmissionError::Dropped {
+                    step: name.clone(),
+                    tx_hash,
+                })?;
+            if receipt.status != Some(U64::from(1)) {
+                return Err(SubmissionError::Reverted {
+                    step: name,
+                    tx_hash,
+                }
+                .into());
+            }
+
+            state.upsert(StepRecord {
+                step: name,
+                tx_hash: Some(tx_hash),
+                facts,
+                status: StepStatus::Confirmed,
+                replaced,
+            });
+            state.save(&self.state_file)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Builds the contract call of `step`.
+    fn call<M: Middleware>(
+        &self,
+        step: &Step,
+        signer: Arc<M>,
+    ) -> Result<ContractFunctionCall<M>, AdapterError> {
+        let main_proof = &self.split_proofs.main_proof;
+        let call = match step {
+            Step::MerkleStatement(name) => self.split_proofs.merkle_statements[name]
+                .verify(self.contracts.merkle_statement, signer),
+            Step::FriStatement(layer) => self.split_proofs.fri_merkle_statements[*layer]
+                .verify(self.contracts.fri_statement, signer),
+            Step::ContinuousMemoryPage(index) => main_proof.register_continuous_memory_page(
+                self.contracts.memory_page_fact_registry,
+                signer,
+                self.continuous_pages[*index].clone(),
+            ),
+            Step::MainProof => main_proof.verify(
+                self.contracts.gps_statement_verifier,
+                signer,
+                self.task_metadata()?,
+            )?,
+        };
+        Ok(call)
+    }
+
+    /// Waits for a transaction recorded as pending until it is mined, returning its receipt, or
+    /// dropped from the mempool, returning `None`.
+    async fn wait_for_pending<M: Middleware>(
+        signer: &M,
+        step: &str,
+        tx_hash: H256,
+    ) -> Result<Option<TransactionReceipt>, SubmissionError> {
+        PendingTransaction::new(tx_hash, signer.provider())
+            .await
+            .map_err(|e| SubmissionError::Provider {
+                step: step.to_string(),
+                message: e.to_string(),
+            })
+    }
+}
+
+fn decode_revert_message<M: Middleware>(e: ContractError<M>) -> String {
+    match e {
+        ContractError::Revert(err) => err.to_string(),
+        _ => e.to_string(),
+    }
+}
@@ -0,0 +1,205 @@
+use ethers::{
+    types::{H256, U256},
+    utils::{hex, keccak256},
+};
+
+use crate::{
+    annotated_proof::{AnnotatedProof, ProofParameters},
+    annotation::{parse_annotations, Annotation, Direction},
+    errors::{
+        AdapterError, ParseError, ProofParametersError, ProofStructureError, TranscriptMismatch,
+    },
+    field::Felt252,
+    keccak_words,
+    oods_statement::MainProof,
+};
+
+/// Magic prefix hashed with the channel digest when checking the proof of work.
+const POW_PREFIX: u64 = 0x0123456789abcded;
+
+/// Random field elements are sampled from `[0, 31 * prime)` to keep the rejection rate low.
+const RANDOM_FIELD_ELEMENT_BOUND: &str =
+    "f80000000000020f00000000000000000000000000000000000000000000001f";
+
+/// A keccak based Fiat-Shamir channel, as implemented by stone's `NonInteractiveVerifierChannel`
+/// and the `VerifierChannel` contract.
+#[derive(Clone, Debug)]
+pub struct Channel {
+    digest: H256,
+    counter: U256,
+}
+
+impl Channel {
+    pub fn new(seed: H256) -> Channel {
+        Channel {
+            digest: seed,
+            counter: U256::zero(),
+        }
+    }
+
+    /// Mix data sent by the prover into the channel digest.
+    pub fn mix(&mut self, data: &[u8]) {
+        let mut preimage = vec![0u8; 32];
+        (U256::from_big_endian(self.digest.as_bytes()) + 1).to_big_endian(&mut preimage);
+        preimage.extend_from_slice(data);
+        self.digest = H256::from(keccak256(preimage));
+        self.counter = U256::zero();
+    }
+
+    /// Draw the next 32 random bytes of the channel.
+    pub fn random_bytes(&mut self) -> U256 {
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(self.digest.as_bytes());
+        self.counter.to_big_endian(&mut preimage[32..]);
+        self.counter += U256::one();
+        U256::from_big_endian(&keccak256(preimage))
+    }
+
+    /// Draw a random field element, in standard (non-montgomery) form.
+    pub fn random_field_element(&mut self) -> U256 {
+        let bound = U256::from_str_radix(RANDOM_FIELD_ELEMENT_BOUND, 16)
+            .expect("bound is a valid hex constant");
+        let mut value = self.random_bytes();
+        while value >= bound {
+            value = self.random_bytes();
+        }
+        Felt252::from_montgomery(value).into()
+    }
+
+    /// Draw `count` query indices below `mask + 1`, four per 32 random bytes, in the order the
+    /// prover receives them.
+    pub fn random_queries(&mut self, count: usize, mask: u64) -> Vec<u64> {
+        let mut queries = Vec::with_capacity(count);
+        while queries.len() < count {
+            let bytes = self.random_bytes();
+            for shift in [192, 128, 64, 0] {
+                if queries.len() == count {
+                    break;
+                }
+                queries.push((bytes >> shift).low_u64() & mask);
+            }
+        }
+        queries
+    }
+
+    /// Check the proof of work `nonce` against the current digest, then mix it into the channel.
+    pub fn verify_proof_of_work(&mut self, nonce: &[u8], bits: u8) -> bool {
+        let mut preimage = POW_PREFIX.to_be_bytes().to_vec();
+        preimage.extend_from_slice(self.digest.as_bytes());
+        preimage.push(bits);
+        let mut preimage = keccak256(preimage).to_vec();
+        preimage.extend_from_slice(nonce);
+        let work = U256::from_big_endian(&keccak256(preimage));
+
+        let valid = bits == 0 || work < U256::one() << (256 - bits as usize);
+        self.mix(nonce);
+        valid
+    }
+}
+
+/// Mask of the query indices, which are drawn from an evaluation domain of
+/// `2^(log_n_cosets + sum(fri_step_list) + log2(last_layer_degree_bound))` points.
+fn query_mask(proof_parameters: &ProofParameters) -> Result<u64, ProofParametersError> {
+    let fri = &proof_parameters.stark.fri;
+    let log_size = fri
+        .fri_step_list
+        .iter()
+        .chain([
+            &proof_parameters.stark.log_n_cosets,
+            &fri.last_layer_degree_bound
+                .next_power_of_two()
+                .trailing_zeros(),
+        ])
+        .fold(0u32, |sum, &log| sum.saturating_add(log));
+    1u64.checked_shl(log_size)
+        .map(|size| size - 1)
+        .ok_or(ProofParametersError::EvaluationDomainTooLarge { log_size })
+}
+
+/// Replay the Fiat-Shamir transcript of an annotated proof.
+///
+/// The prover to verifier ranges of `proof_hex` are fed through a [Channel] seeded with the
+/// public input, and every verifier to prover value in the annotations is recomputed and compared.
+/// Returns the mismatches found, which is empty when the annotations agree with the proof.
+pub fn replay_transcript(
+    annotated_proof: &AnnotatedProof,
+) -> Result<Vec<TranscriptMismatch>, AdapterError> {
+    annotated_proof.proof_parameters.check_field()?;
+    let proof = hex::decode(&annotated_proof.proof_hex).map_err(ParseError::from)?;
+    let annotations = parse_annotations(&annotated_proof.annotations, "annotations")?;
+
+    let seed = keccak_words(&MainProof::public_input_words(
+        &annotated_proof.public_input,
+    )?);
+    let mut channel = Channel::new(seed);
+
+    let fri = &annotated_proof.proof_parameters.stark.fri;
+    let pow_bits = fri.proof_of_work_bits as u8;
+    let query_mask = query_mask(&annotated_proof.proof_parameters)?;
+    let mut queries = Vec::new();
+    let mut next_query = 0;
+
+    let mut mismatches = Vec::new();
+
+    for (index, annotation) in annotations.iter().enumerate() {
+        let line = index + 1;
+        let direction = match annotation.direction() {
+            Some(direction) => direction,
+            None => continue,
+        };
+
+        match direction {
+            Direction::ProverToVerifier { start, end } => {
+                let data =
+                    proof
+                        .get(start..end)
+                        .ok_or(ProofStructureError::ProofRangeOutOfBounds {
+                            line,
+                            start,
+                            end,
+                            proof_len: proof.len(),
+                        })?;
+                match annotation {
+                    Annotation::ProofOfWorkNonce { .. } => {
+                        if !channel.verify_proof_of_work(data, pow_bits) {
+                            mismatches.push(TranscriptMismatch::ProofOfWork { line });
+                        }
+                        queries = channel.random_queries(fri.n_queries as usize, query_mask);
+                    }
+                    _ => channel.mix(data),
+                }
+            }
+            Direction::VerifierToProver => match annotation {
+                Annotation::InteractionElement { value, .. }
+                | Annotation::ConstraintRandomElement { value, .. }
+                | Annotation::EvaluationPoint { value, .. } => {
+                    let computed = channel.random_field_element();
+                    if computed != *value {
+                        mismatches.push(TranscriptMismatch::FieldElement {
+                            line,
+                            annotated: *value,
+                            computed,
+                        });
+                    }
+                }
+                Annotation::QueryIndex { query, .. } => {
+                    match queries.get(next_query) {
+                        Some(computed) if computed != query => {
+                            mismatches.push(TranscriptMismatch::QueryIndex {
+                                line,
+                                annotated: *query,
+                                computed: *computed,
+                            })
+                        }
+                        Some(_) => {}
+                        None => mismatches.push(TranscriptMismatch::ExtraQueryIndex { line }),
+                    }
+                    next_query += 1;
+                }
+                _ => {}
+            },
+        }
+    }
+
+    Ok(mismatches)
+}
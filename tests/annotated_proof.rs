@@ -0,0 +1,246 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+use stark_evm_adapter::annotated_proof::AnnotatedProof;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+    use stark_evm_adapter::annotated_proof::{BuiltinValue, PrivateInput, PublicInput};
+    use stark_evm_adapter::annotation_parser::split_fri_merkle_statements;
+    use stark_evm_adapter::errors::{AdapterError, ProofStructureError, PublicInputError};
+
+    use crate::common::get_bootloader_proof;
+
+    const PROOF: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/stone_proof.json"
+    ));
+    const ANNOTATIONS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/stone_proof_annotation.txt"
+    ));
+    const EXTRA_ANNOTATIONS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/stone_proof_annotation_extra.txt"
+    ));
+
+    #[test]
+    fn test_from_stone_files() {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+        let annotated_proof = AnnotatedProof::from_stone_files(
+            format!("{}/stone_proof.json", fixtures),
+            format!("{}/stone_proof_annotation.txt", fixtures),
+            format!("{}/stone_proof_annotation_extra.txt", fixtures),
+        )
+        .unwrap();
+        assert_eq!(
+            annotated_proof.annotations.len(),
+            ANNOTATIONS.lines().count()
+        );
+        let private_input = annotated_proof.private_input.as_ref().unwrap();
+        assert!(private_input.trace_path.ends_with("fibonacci_trace.json"));
+        assert!(private_input.builtins["pedersen"].is_empty());
+        let prover_config = annotated_proof.prover_config.as_ref().unwrap();
+        assert!(!prover_config.cached_lde_config.store_full_lde);
+        assert_eq!(
+            annotated_proof.version.as_ref().unwrap().statement_name,
+            "INVALID_NAME"
+        );
+        assert!(annotated_proof.other.is_empty());
+        assert_eq!(
+            annotated_proof.public_input.public_memory[0].value,
+            U256::from(0x40780017fff7fffu64)
+        );
+
+        // the merged proof round-trips the fields of the stone proof file
+        let merged = serde_json::to_value(&annotated_proof).unwrap();
+        let proof: serde_json::Value = serde_json::from_str(PROOF).unwrap();
+        for (key, value) in proof.as_object().unwrap() {
+            assert_eq!(&merged[key], value, "field {}", key);
+        }
+
+        let split_proofs = split_fri_merkle_statements(annotated_proof).unwrap();
+        split_proofs.verify_locally().unwrap();
+    }
+
+    #[test]
+    fn test_proof_length_mismatch() {
+        let mut proof: serde_json::Value = serde_json::from_str(PROOF).unwrap();
+        let proof_hex = proof["proof_hex"].as_str().unwrap().to_string();
+        proof["proof_hex"] = serde_json::json!(&proof_hex[..proof_hex.len() - 64]);
+        let proof = serde_json::to_string(&proof).unwrap();
+
+        match AnnotatedProof::from_readers(
+            proof.as_bytes(),
+            ANNOTATIONS.as_bytes(),
+            EXTRA_ANNOTATIONS.as_bytes(),
+        ) {
+            Err(AdapterError::ProofStructure(ProofStructureError::ProofLengthMismatch {
+                proof_len,
+                annotated_len,
+            })) => assert_eq!(proof_len + 32, annotated_len),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_unknown_extra_merkle() {
+        let extra_annotations =
+            EXTRA_ANNOTATIONS.replace("/Trace 0: For node", "/Trace 9: For node");
+
+        match AnnotatedProof::from_readers(
+            PROOF.as_bytes(),
+            ANNOTATIONS.as_bytes(),
+            extra_annotations.as_bytes(),
+        ) {
+            Err(AdapterError::ProofStructure(ProofStructureError::UnknownExtraMerkles {
+                names,
+            })) => assert_eq!(names, vec!["Trace 9".to_string()]),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_annotated_proof_round_trip() {
+        for file in [
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/annotated_proof.json"
+            )),
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/examples/bootloader/fib_annotated_proof.json"
+            )),
+        ] {
+            let annotated_proof: AnnotatedProof = serde_json::from_str(file).unwrap();
+            assert!(annotated_proof.private_input.is_some());
+            assert!(annotated_proof.prover_config.is_some());
+            assert!(annotated_proof.version.is_some());
+
+            let expected: serde_json::Value = serde_json::from_str(file).unwrap();
+            assert_eq!(serde_json::to_value(&annotated_proof).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_private_input_builtins() {
+        let json = serde_json::json!({
+            "trace_path": "trace.bin",
+            "memory_path": "memory.bin",
+            "range_check": [{"index": 0, "value": "0x7fff"}],
+            "ecdsa": [{
+                "index": 3,
+                "pubkey": "0x1ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca",
+                "msg": "0x0",
+                "signature_input": {"r": "0x2", "w": "0x3"}
+            }],
+            "layout_params": {"n_steps": 16},
+            "keccak": [{"index": 0, "input": ["0x1", "0x2"]}]
+        });
+        let private_input: PrivateInput = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(
+            private_input.builtins.keys().collect::<Vec<_>>(),
+            ["ecdsa", "range_check"]
+        );
+        assert_eq!(
+            private_input.other.keys().collect::<Vec<_>>(),
+            ["keccak", "layout_params"]
+        );
+
+        let ecdsa = &private_input.builtins["ecdsa"][0];
+        assert_eq!(ecdsa.index, 3);
+        assert_eq!(ecdsa.values["msg"], BuiltinValue::Felt(U256::zero()));
+        match &ecdsa.values["signature_input"] {
+            BuiltinValue::Group(values) => assert_eq!(values["w"], U256::from(3)),
+            other => panic!("unexpected value: {:?}", other),
+        }
+        assert_eq!(serde_json::to_value(&private_input).unwrap(), json);
+    }
+
+    #[test]
+    fn test_validate_public_input() {
+        let annotated_proof = get_bootloader_proof();
+        assert_eq!(annotated_proof.public_input.validate(), Ok(()));
+
+        let mut json = serde_json::to_value(&annotated_proof.public_input).unwrap();
+        json["memory_segments"]["output"]["stop_ptr"] = 2100.into();
+        json["memory_segments"]["range_check"]["begin_addr"] = 2200.into();
+        json["n_steps"] = 100.into();
+        json["rc_min"] = 40000.into();
+        let public_memory = json["public_memory"].as_array_mut().unwrap();
+        public_memory[1]["address"] = 1.into();
+        // Leave a gap between the first two cells of page 1.
+        let first = public_memory
+            .iter()
+            .position(|cell| cell["page"] == 1)
+            .unwrap();
+        let gap_address = public_memory[first]["address"].as_u64().unwrap() + 2;
+        public_memory[first + 1]["address"] = gap_address.into();
+        let public_input: PublicInput = serde_json::from_value(json).unwrap();
+
+        let violations = match public_input.validate() {
+            Err(PublicInputError::Invalid { violations }) => violations,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(
+            violations,
+            vec![
+                PublicInputError::InvalidSegment {
+                    name: "output".to_string(),
+                    begin_addr: 2115,
+                    stop_ptr: 2100,
+                },
+                PublicInputError::OverlappingSegments {
+                    first: "pedersen".to_string(),
+                    second: "range_check".to_string(),
+                },
+                PublicInputError::NStepsNotPowerOfTwo { n_steps: 100 },
+                PublicInputError::InvalidRangeCheckBounds {
+                    rc_min: 40000,
+                    rc_max: 32796,
+                },
+                PublicInputError::DuplicateAddress { address: 1 },
+                PublicInputError::NonContinuousPage {
+                    page: 1,
+                    address: U256::from(gap_address),
+                },
+            ]
+        );
+        assert!(PublicInputError::Invalid { violations }
+            .to_string()
+            .contains("n_steps 100 is not a power of two; rc_min"));
+
+        let mut annotated_proof = annotated_proof;
+        annotated_proof.public_input = public_input;
+        assert!(matches!(
+            split_fri_merkle_statements(annotated_proof),
+            Err(AdapterError::PublicInput(PublicInputError::Invalid { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_validate_missing_pages() {
+        let proof: serde_json::Value = serde_json::from_str(PROOF).unwrap();
+        let mut public_input: PublicInput =
+            serde_json::from_value(proof["public_input"].clone()).unwrap();
+        let last = public_input.public_memory.len() - 1;
+        public_input.public_memory[last].page = 2;
+        assert_eq!(
+            public_input.validate(),
+            Err(PublicInputError::Invalid {
+                violations: vec![PublicInputError::MissingPage { page: 1 }],
+            })
+        );
+
+        public_input.public_memory.clear();
+        assert_eq!(
+            public_input.validate(),
+            Err(PublicInputError::Invalid {
+                violations: vec![PublicInputError::EmptyPublicMemory],
+            })
+        );
+    }
+}
@@ -0,0 +1,132 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+use stark_evm_adapter::annotation::{parse_annotations, Annotation, Direction};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+    use stark_evm_adapter::errors::ParseError;
+
+    use crate::common::get_annotated_proof;
+
+    fn path(path: &str) -> Vec<String> {
+        path.split('/').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_annotation_lines() {
+        let commitment: Annotation = "P->V[8768:8800]: /cpu air/STARK/FRI/Commitment/Layer 1: Commitment: Hash(0x994586a93d3f0397b588be7eb5ea55ecaec10145000000000000000000000000)".parse().unwrap();
+        assert_eq!(
+            commitment,
+            Annotation::Commitment {
+                direction: Some(Direction::ProverToVerifier {
+                    start: 8768,
+                    end: 8800
+                }),
+                path: path("cpu air/STARK/FRI/Commitment/Layer 1"),
+                hash: U256::from_str_radix(
+                    "994586a93d3f0397b588be7eb5ea55ecaec10145000000000000000000000000",
+                    16
+                )
+                .unwrap(),
+            }
+        );
+        assert_eq!(commitment.proof_range(), Some(8768..8800));
+
+        let trace: Annotation = "P->V[9224:9256]: /cpu air/STARK/FRI/Decommitment/Layer 0/Virtual Oracle/Trace 0: Row 281548, Column 0: Field Element(0x535cef)".parse().unwrap();
+        assert!(matches!(
+            trace,
+            Annotation::TraceElement {
+                row: 281548,
+                column: 0,
+                ..
+            }
+        ));
+        assert_eq!(trace.name(), Some("Trace 0"));
+
+        let fri: Annotation =
+            "/cpu air/STARK/FRI/Decommitment/Layer 1: Row 35193, Column 4: Field Element(0x5939)"
+                .parse()
+                .unwrap();
+        assert!(matches!(
+            fri,
+            Annotation::FriElement {
+                direction: None,
+                row: 35193,
+                column: 4,
+                ..
+            }
+        ));
+
+        let query: Annotation = "V->P: /cpu air/STARK/FRI/QueryIndices: 0: Number(8381529)"
+            .parse()
+            .unwrap();
+        assert!(matches!(
+            query,
+            Annotation::QueryIndex {
+                direction: Some(Direction::VerifierToProver),
+                index: 0,
+                query: 8381529,
+                ..
+            }
+        ));
+
+        let coefficients: Annotation = "P->V[8960:9024]: /cpu air/STARK/FRI/Commitment/Last Layer: Coefficients: Field Elements(0x1, 0x2)".parse().unwrap();
+        assert!(matches!(
+            coefficients,
+            Annotation::LastLayerCoefficients { ref values, .. } if values == &[U256::one(), U256::from(2)]
+        ));
+
+        let pow: Annotation =
+            "P->V[9216:9224]: /cpu air/STARK/FRI/Proof of Work: POW: Data(0xde8310b)"
+                .parse()
+                .unwrap();
+        assert!(
+            matches!(pow, Annotation::ProofOfWorkNonce { nonce, .. } if nonce == U256::from(0xde8310bu64))
+        );
+
+        for comment in ["title cpu air Proof Protocol", "", "Byte count: 159976"] {
+            assert!(matches!(
+                comment.parse::<Annotation>().unwrap(),
+                Annotation::Comment { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unknown_annotation_position() {
+        let lines = vec![
+            "title cpu air Proof Protocol",
+            "V->P: /cpu air/STARK/FRI/QueryIndices: 0: Number(8381529)",
+            "V->P: /cpu air/STARK/FRI: Something new: Field Element(0x1)",
+        ];
+        match parse_annotations(&lines, "annotations") {
+            Err(ParseError::AtLine {
+                section,
+                line,
+                source,
+            }) => {
+                assert_eq!(section, "annotations");
+                assert_eq!(line, 3);
+                assert!(matches!(*source, ParseError::UnknownAnnotation(_)));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_fixture_annotations() {
+        let annotated_proof = get_annotated_proof();
+
+        let annotations = parse_annotations(&annotated_proof.annotations, "annotations").unwrap();
+        assert_eq!(annotations.len(), annotated_proof.annotations.len());
+        let extra_annotations =
+            parse_annotations(&annotated_proof.extra_annotations, "extra annotations").unwrap();
+        assert!(extra_annotations
+            .iter()
+            .all(|annotation| annotation.direction().is_none()));
+    }
+}
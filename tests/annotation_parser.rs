@@ -1,28 +1,25 @@
 extern crate stark_evm_adapter;
 
-use stark_evm_adapter::annotation_parser::split_fri_merkle_statements;
+mod common;
+
+use stark_evm_adapter::annotation_parser::{
+    split_fri_merkle_statements, split_stone_files, split_stone_readers,
+};
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use ethers::types::U256;
     use stark_evm_adapter::annotated_proof::AnnotatedProof;
-    use stark_evm_adapter::annotation_parser::SplitProofs;
+    use stark_evm_adapter::errors::{
+        AdapterError, ParseError, ProofParametersError, ProofStructureError,
+    };
     use stark_evm_adapter::fri_merkle_statement::VerifyFRICall;
     use stark_evm_adapter::merkle_statement::VerifyMerkleCall;
     use stark_evm_adapter::oods_statement::VerifyProofAndRegisterCall;
+    use stark_evm_adapter::transcript::replay_transcript;
 
-    fn get_split_proofs() -> SplitProofs {
-        let proof_file = include_str!(concat!(
-            env!("CARGO_MANIFEST_DIR"),
-            "/tests/fixtures/annotated_proof.json"
-        ));
-        let annotated_proof: AnnotatedProof = serde_json::from_str(proof_file).unwrap();
-
-        // create the split proof
-        let split_proofs: SplitProofs = split_fri_merkle_statements(annotated_proof).unwrap();
-        split_proofs
-    }
+    use crate::common::{get_annotated_proof, get_split_proofs};
 
     #[test]
     fn test_trace_merkle_contract_args() {
@@ -71,7 +68,8 @@ mod tests {
         let split_proofs = get_split_proofs();
         let main_proof_contract_args = split_proofs
             .main_proof
-            .contract_function_call(vec![U256::zero()]);
+            .contract_function_call(vec![U256::zero()])
+            .unwrap();
 
         let main_proof_contract_args_file = include_str!(concat!(
             env!("CARGO_MANIFEST_DIR"),
@@ -81,4 +79,273 @@ mod tests {
             serde_json::from_str(main_proof_contract_args_file).unwrap();
         assert_json_diff::assert_json_eq!(main_proof_contract_args, deser_main_proof_contract_args);
     }
+
+    #[test]
+    fn test_merkle_names_mismatch() {
+        let mut annotated_proof = get_annotated_proof();
+        annotated_proof
+            .extra_annotations
+            .retain(|line| !line.contains("/Trace 1:"));
+
+        match split_fri_merkle_statements(annotated_proof) {
+            Err(AdapterError::ProofStructure(ProofStructureError::MerkleNamesMismatch {
+                only_in_annotations,
+                only_in_extra_annotations,
+            })) => {
+                assert_eq!(only_in_annotations, vec!["Trace 1".to_string()]);
+                assert!(only_in_extra_annotations.is_empty());
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_invalid_annotation_line_number() {
+        let mut annotated_proof = get_annotated_proof();
+        let line = annotated_proof
+            .annotations
+            .iter()
+            .position(|line| line.contains("Decommitment") && line.contains("Hash"))
+            .unwrap();
+        annotated_proof.annotations[line] =
+            "P->V[0:32]: /cpu air/STARK/FRI/Decommitment/Layer 0/Virtual Oracle/Trace 0: For node x: Hash(0x01)".to_string();
+
+        match split_fri_merkle_statements(annotated_proof) {
+            Err(AdapterError::Parse(ParseError::AtLine {
+                section,
+                line: reported,
+                ..
+            })) => {
+                assert_eq!(section, "annotations");
+                assert_eq!(reported, line + 1);
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_split_stone_readers() {
+        let annotated_proof = get_annotated_proof();
+        // The annotated proof file has the fields of the stone proof file, plus the annotations.
+        let proof_json = serde_json::to_string(&annotated_proof).unwrap();
+        let annotations = annotated_proof.annotations.join("\r\n");
+        let extra_annotations = annotated_proof.extra_annotations.join("\n");
+
+        let streamed = split_stone_readers(
+            proof_json.as_bytes(),
+            annotations.as_bytes(),
+            extra_annotations.as_bytes(),
+        )
+        .unwrap();
+        let split_proofs = split_fri_merkle_statements(annotated_proof).unwrap();
+        assert_json_diff::assert_json_eq!(streamed, split_proofs);
+    }
+
+    #[test]
+    fn test_split_stone_files() {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+        let files = [
+            format!("{}/stone_proof.json", fixtures),
+            format!("{}/stone_proof_annotation.txt", fixtures),
+            format!("{}/stone_proof_annotation_extra.txt", fixtures),
+        ];
+
+        let streamed = split_stone_files(&files[0], &files[1], &files[2]).unwrap();
+        streamed.verify_locally().unwrap();
+        let annotated_proof =
+            AnnotatedProof::from_stone_files(&files[0], &files[1], &files[2]).unwrap();
+        let split_proofs = split_fri_merkle_statements(annotated_proof).unwrap();
+        assert_json_diff::assert_json_eq!(streamed, split_proofs);
+    }
+
+    #[test]
+    fn test_split_stone_readers_errors() {
+        let annotated_proof = get_annotated_proof();
+        let proof_json = serde_json::to_string(&annotated_proof).unwrap();
+        let annotations = annotated_proof.annotations.join("\n");
+
+        let mut extra_annotations = annotated_proof.extra_annotations.clone();
+        extra_annotations[2] =
+            "/cpu air/STARK/Out Of Domain Sampling: For node x: Hash(0x01)".into();
+        match split_stone_readers(
+            proof_json.as_bytes(),
+            annotations.as_bytes(),
+            extra_annotations.join("\n").as_bytes(),
+        ) {
+            Err(AdapterError::Parse(ParseError::AtLine { section, line, .. })) => {
+                assert_eq!(section, "extra annotations");
+                assert_eq!(line, 3);
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        // The annotations must cover the whole proof.
+        let mut proof: serde_json::Value = serde_json::from_str(&proof_json).unwrap();
+        let proof_hex = proof["proof_hex"].as_str().unwrap().to_string();
+        proof["proof_hex"] = serde_json::json!(format!("{}00", proof_hex));
+        match split_stone_readers(
+            proof.to_string().as_bytes(),
+            annotations.as_bytes(),
+            annotated_proof.extra_annotations.join("\n").as_bytes(),
+        ) {
+            Err(AdapterError::ProofStructure(ProofStructureError::ProofLengthMismatch {
+                proof_len,
+                annotated_len,
+            })) => assert_eq!(proof_len, annotated_len + 1),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_split_fri_merkle_statements_length_mismatch() {
+        let mut annotated_proof = get_annotated_proof();
+        annotated_proof.proof_hex.push_str("00");
+        match split_fri_merkle_statements(annotated_proof) {
+            Err(AdapterError::ProofStructure(ProofStructureError::ProofLengthMismatch {
+                proof_len,
+                annotated_len,
+            })) => assert_eq!(proof_len, annotated_len + 1),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    fn proof_parameters_error(
+        edit: impl FnOnce(&mut AnnotatedProof),
+    ) -> Option<ProofParametersError> {
+        let mut annotated_proof = get_annotated_proof();
+        edit(&mut annotated_proof);
+        match split_fri_merkle_statements(annotated_proof) {
+            Err(AdapterError::ProofParameters(e)) => Some(e),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_proof_parameters_mismatch() {
+        assert_eq!(proof_parameters_error(|_| {}), None);
+
+        assert_eq!(
+            proof_parameters_error(|proof| proof.proof_parameters.stark.fri.n_queries = 34),
+            Some(ProofParametersError::QueryCount {
+                declared: 34,
+                found: 33
+            })
+        );
+        assert_eq!(
+            proof_parameters_error(|proof| {
+                proof.proof_parameters.stark.fri.last_layer_degree_bound = 12
+            }),
+            Some(ProofParametersError::InvalidLastLayerDegreeBound(12))
+        );
+        assert_eq!(
+            proof_parameters_error(|proof| {
+                proof.proof_parameters.stark.fri.fri_step_list = vec![0, 3, 3, 3, 3, 3, 2, 1]
+            }),
+            Some(ProofParametersError::FriLayerCount {
+                declared: 7,
+                found: 6
+            })
+        );
+        assert_eq!(
+            proof_parameters_error(|proof| {
+                proof.proof_parameters.stark.fri.fri_step_list = vec![0, 2, 4, 3, 3, 3, 3]
+            }),
+            Some(ProofParametersError::FriStepSize {
+                layer: 0,
+                declared: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            proof_parameters_error(|proof| proof.proof_parameters.stark.log_n_cosets = 3),
+            Some(ProofParametersError::MerkleHeight {
+                name: "Trace 0".to_string(),
+                expected: 24,
+                found: 23
+            })
+        );
+        let error = proof_parameters_error(|proof| proof.proof_parameters.stark.log_n_cosets = 70);
+        assert!(
+            matches!(
+                error,
+                Some(ProofParametersError::QueryOutOfRange { height: 91, .. })
+            ),
+            "{error:?}"
+        );
+        // Same evaluation domain, with a last layer of a different degree.
+        assert_eq!(
+            proof_parameters_error(|proof| {
+                proof.proof_parameters.stark.fri.last_layer_degree_bound = 16;
+                proof.proof_parameters.stark.log_n_cosets = 1;
+            }),
+            Some(ProofParametersError::LastLayerCoefficients {
+                declared: 16,
+                found: 8
+            })
+        );
+        // Drop a query that is decommitted in the trace.
+        assert_eq!(
+            proof_parameters_error(|proof| {
+                let query = proof
+                    .annotations
+                    .iter()
+                    .position(|line| line.contains("/QueryIndices: 0: Number("))
+                    .unwrap();
+                proof.annotations.remove(query);
+                proof.proof_parameters.stark.fri.n_queries = 32;
+            }),
+            Some(ProofParametersError::MerkleQueryCount {
+                name: "Trace 0".to_string(),
+                expected: 32,
+                found: 33
+            })
+        );
+    }
+
+    #[test]
+    fn test_extension_field_is_rejected() {
+        let mut annotated_proof = get_annotated_proof();
+        annotated_proof.proof_parameters.use_extension_field = true;
+        // The annotations of such a proof hold pairs of coordinates, which are never parsed.
+        annotated_proof.annotations[0] =
+            "P->V[0:64]: /cpu air/STARK: Unknown: Pair(0x1, 0x2)".into();
+
+        assert!(matches!(
+            replay_transcript(&annotated_proof),
+            Err(AdapterError::ProofParameters(
+                ProofParametersError::ExtensionField
+            ))
+        ));
+
+        let proof_json = serde_json::to_string(&annotated_proof).unwrap();
+        let annotations = annotated_proof.annotations.join("\n");
+        let extra_annotations = annotated_proof.extra_annotations.join("\n");
+        assert!(matches!(
+            split_stone_readers(
+                proof_json.as_bytes(),
+                annotations.as_bytes(),
+                extra_annotations.as_bytes(),
+            ),
+            Err(AdapterError::ProofParameters(
+                ProofParametersError::ExtensionField
+            ))
+        ));
+        assert!(matches!(
+            AnnotatedProof::from_readers(
+                proof_json.as_bytes(),
+                annotations.as_bytes(),
+                extra_annotations.as_bytes(),
+            ),
+            Err(AdapterError::ProofParameters(
+                ProofParametersError::ExtensionField
+            ))
+        ));
+        assert!(matches!(
+            split_fri_merkle_statements(annotated_proof),
+            Err(AdapterError::ProofParameters(
+                ProofParametersError::ExtensionField
+            ))
+        ));
+    }
 }
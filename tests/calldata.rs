@@ -0,0 +1,151 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use ethers::{
+        abi::AbiDecode,
+        contract::EthCall,
+        types::{Address, U256},
+    };
+    use stark_evm_adapter::{
+        calldata::{export_calls, safe_batch, safe_batches, SAFE_BATCH_GAS_LIMIT},
+        fri_merkle_statement::VerifyFRICall,
+        gas::GasModel,
+        merkle_statement::VerifyMerkleCall,
+        oods_statement::VerifyProofAndRegisterCall,
+    };
+
+    use crate::common::get_pipeline;
+
+    #[test]
+    fn test_export_calls() {
+        let pipeline = get_pipeline(Path::new("unused.json"));
+        let calls = export_calls(&pipeline).unwrap();
+
+        let steps = pipeline.steps();
+        assert_eq!(calls.len(), steps.len());
+        for (call, step) in calls.iter().zip(&steps) {
+            assert_eq!(call.step, step.name());
+            assert_eq!(call.to, pipeline.target(step));
+            assert_eq!(call.selector[..], call.data[..4]);
+        }
+
+        // The calldata decodes back to the contract calls of the statements.
+        let merkle = &calls[0];
+        assert_eq!(merkle.function, VerifyMerkleCall::abi_signature());
+        assert_eq!(
+            VerifyMerkleCall::decode(&merkle.data).unwrap(),
+            pipeline.split_proofs.merkle_statements["Trace 0"].contract_function_call()
+        );
+
+        let n_traces = pipeline.split_proofs.merkle_statements.len();
+        assert_eq!(
+            VerifyFRICall::decode(&calls[n_traces].data).unwrap(),
+            pipeline.split_proofs.fri_merkle_statements[0].contract_function_call()
+        );
+
+        let main = calls.last().unwrap();
+        assert_eq!(main.step, "Main proof");
+        assert_eq!(main.function, VerifyProofAndRegisterCall::abi_signature());
+        assert!(VerifyProofAndRegisterCall::decode(&main.data).is_ok());
+    }
+
+    #[test]
+    fn test_safe_batch() {
+        let pipeline = get_pipeline(Path::new("unused.json"));
+        let calls = export_calls(&pipeline).unwrap();
+        let safe = Address::random();
+        let batch = serde_json::to_value(safe_batch(&calls, 11155111, Some(safe))).unwrap();
+
+        assert_eq!(batch["version"], "1.0");
+        assert_eq!(batch["chainId"], "11155111");
+        assert_eq!(
+            batch["meta"]["createdFromSafeAddress"],
+            serde_json::to_value(safe).unwrap()
+        );
+
+        let transactions = batch["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), calls.len());
+        for (transaction, call) in transactions.iter().zip(&calls) {
+            assert_eq!(transaction["to"], serde_json::to_value(call.to).unwrap());
+            assert_eq!(transaction["value"], "0");
+            assert_eq!(
+                transaction["data"],
+                serde_json::to_value(&call.data).unwrap()
+            );
+            assert!(transaction["contractMethod"].is_null());
+        }
+    }
+
+    #[test]
+    fn test_safe_batches() {
+        let pipeline = get_pipeline(Path::new("unused.json"));
+        let calls = export_calls(&pipeline).unwrap();
+        let model = GasModel::default();
+        let gas: Vec<U256> = calls
+            .iter()
+            .zip(pipeline.steps())
+            .map(|(call, step)| model.gas(&step, &call.data))
+            .collect();
+
+        let batches = |gas_limit: U256| {
+            safe_batches(&pipeline, &model, gas_limit, 1, None)
+                .unwrap()
+                .iter()
+                .map(|batch| serde_json::to_value(batch).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // The whole submission fits in a single batch.
+        let total: U256 = gas.iter().fold(U256::zero(), |total, &gas| total + gas);
+        assert!(total < U256::from(SAFE_BATCH_GAS_LIMIT));
+        let single = batches(total);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0]["meta"]["name"], "STARK proof submission");
+        assert_eq!(
+            single[0]["transactions"].as_array().unwrap().len(),
+            calls.len()
+        );
+
+        // Under the gas of the largest step, the batches keep the order of the steps, each
+        // stays under the limit, and the main proof comes last.
+        let gas_limit = *gas.iter().max().unwrap();
+        let split = batches(gas_limit);
+        assert!(split.len() > 1);
+        let mut offset = 0;
+        for (index, batch) in split.iter().enumerate() {
+            assert_eq!(
+                batch["meta"]["name"],
+                format!("STARK proof submission ({}/{})", index + 1, split.len())
+            );
+            let transactions = batch["transactions"].as_array().unwrap();
+            let batch_gas = gas[offset..offset + transactions.len()]
+                .iter()
+                .fold(U256::zero(), |total, &gas| total + gas);
+            assert!(batch_gas <= gas_limit);
+            for (transaction, call) in transactions.iter().zip(&calls[offset..]) {
+                assert_eq!(
+                    transaction["data"],
+                    serde_json::to_value(&call.data).unwrap()
+                );
+            }
+            offset += transactions.len();
+        }
+        assert_eq!(offset, calls.len());
+        assert_eq!(
+            split.last().unwrap()["meta"]["description"]
+                .as_str()
+                .unwrap()
+                .split(", ")
+                .last(),
+            Some("Main proof")
+        );
+
+        // Steps above the limit on their own get a batch each.
+        assert_eq!(batches(U256::zero()).len(), calls.len());
+    }
+}
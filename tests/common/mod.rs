@@ -0,0 +1,101 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ethers::types::Address;
+use stark_evm_adapter::{
+    annotated_proof::AnnotatedProof,
+    annotation_parser::{split_fri_merkle_statements, SplitProofs},
+    submission::{ContractAddresses, Pipeline},
+};
+
+/// The annotated proof in `tests/fixtures`.
+pub fn get_annotated_proof() -> AnnotatedProof {
+    let proof_file = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/annotated_proof.json"
+    ));
+    serde_json::from_str(proof_file).unwrap()
+}
+
+/// The annotated proof of the bootloader example, whose output holds a task.
+pub fn get_bootloader_proof() -> AnnotatedProof {
+    let proof_file = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/bootloader/fib_annotated_proof.json"
+    ));
+    serde_json::from_str(proof_file).unwrap()
+}
+
+pub fn get_split_proofs() -> SplitProofs {
+    split_fri_merkle_statements(get_annotated_proof()).unwrap()
+}
+
+pub fn get_bootloader_split_proofs() -> SplitProofs {
+    split_fri_merkle_statements(get_bootloader_proof()).unwrap()
+}
+
+pub fn random_contracts() -> ContractAddresses {
+    ContractAddresses {
+        merkle_statement: Address::random(),
+        fri_statement: Address::random(),
+        memory_page_fact_registry: Address::random(),
+        gps_statement_verifier: Address::random(),
+    }
+}
+
+/// A pipeline over the bootloader example, keeping its state in `state_file`.
+pub fn get_pipeline(state_file: &Path) -> Pipeline {
+    let split_proofs = get_bootloader_split_proofs();
+    let fact_topologies = split_proofs
+        .main_proof
+        .public_input
+        .fact_topologies(true, &HashMap::new())
+        .unwrap();
+    Pipeline::new(
+        split_proofs,
+        fact_topologies,
+        random_contracts(),
+        state_file,
+    )
+    .unwrap()
+}
+
+/// A fresh directory under the system temp dir, removed when dropped.
+///
+/// The name is made unique per process and per call, so tests running in parallel, or
+/// several `cargo test` runs at once, never share a directory.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "stark_evm_adapter_{}_{}_{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,94 @@
+extern crate stark_evm_adapter;
+
+use stark_evm_adapter::{default_prime, field::Felt252};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{types::U256, utils::keccak256};
+    use num_bigint::BigUint;
+
+    fn to_biguint(value: U256) -> BigUint {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        BigUint::from_bytes_be(&bytes)
+    }
+
+    fn to_u256(value: &BigUint) -> U256 {
+        U256::from_big_endian(&value.to_bytes_be())
+    }
+
+    /// Deterministic 256-bit test values, including edge cases around the prime.
+    fn sample_values() -> Vec<U256> {
+        let prime = default_prime();
+        let mut values = vec![
+            U256::zero(),
+            U256::one(),
+            prime - 1,
+            prime,
+            prime + 1,
+            U256::MAX,
+        ];
+        values.extend((0u8..32).map(|i| U256::from_big_endian(&keccak256([i]))));
+        values
+    }
+
+    #[test]
+    fn test_arithmetic_matches_biguint() {
+        let prime = to_biguint(default_prime());
+        for a in sample_values() {
+            for b in sample_values() {
+                let (x, y) = (to_biguint(a), to_biguint(b));
+                let (fa, fb) = (Felt252::from(a), Felt252::from(b));
+
+                assert_eq!(U256::from(fa * fb), to_u256(&(&x * &y % &prime)));
+                assert_eq!(U256::from(fa + fb), to_u256(&((&x + &y) % &prime)));
+                assert_eq!(
+                    U256::from(fa - fb),
+                    to_u256(&((&x % &prime + &prime - &y % &prime) % &prime))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_inverse_and_pow() {
+        let prime = to_biguint(default_prime());
+        for a in sample_values() {
+            let felt = Felt252::from(a);
+            match felt.inverse() {
+                Some(inverse) => assert_eq!(felt * inverse, Felt252::ONE),
+                None => assert!(felt.is_zero()),
+            }
+
+            let exponent = U256::from_big_endian(&keccak256(a.to_string()));
+            assert_eq!(
+                U256::from(felt.pow(exponent)),
+                to_u256(&to_biguint(a).modpow(&to_biguint(exponent), &prime))
+            );
+        }
+        assert_eq!(Felt252::from(5u64).pow(U256::zero()), Felt252::ONE);
+    }
+
+    #[test]
+    fn test_montgomery_conversion() {
+        let prime = to_biguint(default_prime());
+        let r = BigUint::from(1u8) << 256u32;
+        for a in sample_values() {
+            let felt = Felt252::from(a);
+            let x = to_biguint(a) % &prime;
+
+            assert_eq!(U256::from(felt), to_u256(&x));
+            assert_eq!(felt.to_montgomery(), to_u256(&(&x * &r % &prime)));
+            assert_eq!(Felt252::from_montgomery(felt.to_montgomery()), felt);
+        }
+
+        // the verifier channel draws montgomery values anywhere below 31 * prime
+        let value = default_prime() * 31 - 1;
+        let r_inv = r.modpow(&(&prime - 2u8), &prime);
+        assert_eq!(
+            U256::from(Felt252::from_montgomery(value)),
+            to_u256(&(to_biguint(value) * r_inv % &prime))
+        );
+    }
+}
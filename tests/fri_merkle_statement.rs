@@ -0,0 +1,76 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::Token;
+    use ethers::types::{H256, U256};
+    use ethers::utils::keccak256;
+    use stark_evm_adapter::errors::{FriVerificationError, StatementVerificationError};
+    use stark_evm_adapter::fri_merkle_statement::FRIMerkleStatement;
+
+    use crate::common::get_split_proofs;
+
+    #[test]
+    fn test_fri_statements_verify_locally() {
+        let split_proofs = get_split_proofs();
+        for (layer, statement) in split_proofs.fri_merkle_statements.iter().enumerate() {
+            assert_eq!(statement.verify_locally(), Ok(()), "layer {}", layer);
+        }
+        assert_eq!(split_proofs.verify_locally(), Ok(()));
+    }
+
+    #[test]
+    fn test_tampered_fri_statement_is_rejected() {
+        let mut split_proofs = get_split_proofs();
+        let statement = &split_proofs.fri_merkle_statements[2];
+
+        // Changing the evaluation point breaks the folding of the first coset.
+        let mut json = serde_json::to_value(statement).unwrap();
+        json["evaluation_point"] = serde_json::to_value(U256::from(7)).unwrap();
+        let tampered: FRIMerkleStatement = serde_json::from_value(json).unwrap();
+        let query = statement.output_interleaved[0];
+        match tampered.verify_locally() {
+            Err(FriVerificationError::OutputValueMismatch { query: q, .. }) => assert_eq!(q, query),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Changing a merkle authentication node breaks the root.
+        let mut json = serde_json::to_value(statement).unwrap();
+        let last = statement.proof.len() - 1;
+        json["proof"][last] = serde_json::to_value(U256::from(1)).unwrap();
+        split_proofs.fri_merkle_statements[2] = serde_json::from_value(json).unwrap();
+        match split_proofs.verify_locally() {
+            Err(StatementVerificationError::Fri { layer, source }) => {
+                assert_eq!(layer, 2);
+                assert!(matches!(*source, FriVerificationError::Merkle(_)));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_fri_statement_fact_hash() {
+        let split_proofs = get_split_proofs();
+        let statement = &split_proofs.fri_merkle_statements[0];
+        let call = statement.contract_function_call();
+
+        // The fri queue sent on-chain ends with a zero delimiter which is not hashed.
+        let n_inputs = call.fri_queue.len() - 1;
+        let words = |values: &[U256]| {
+            ethers::abi::encode(&values.iter().cloned().map(Token::Uint).collect::<Vec<_>>())
+        };
+        let input_hash = keccak256(words(&call.fri_queue[..n_inputs]));
+        let output_hash = keccak256(words(&statement.output_interleaved));
+        let expected = H256::from(keccak256(words(&[
+            call.evaluation_point,
+            call.fri_step_size,
+            U256::from_big_endian(&input_hash),
+            U256::from_big_endian(&output_hash),
+            call.expected_root,
+        ])));
+
+        assert_eq!(statement.fact_hash(), expected);
+    }
+}
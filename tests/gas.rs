@@ -0,0 +1,138 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::TryFrom, path::Path};
+
+    use ethers::{
+        contract::EthCall,
+        providers::{Http, JsonRpcError, MockProvider, MockResponse, Provider},
+        types::U256,
+    };
+    use stark_evm_adapter::{
+        errors::{AdapterError, SubmissionError},
+        gas::{self, calldata_gas, GasModel, GasSource, TX_BASE_GAS},
+        merkle_statement::VerifyMerkleCall,
+        oods_statement::VerifyProofAndRegisterCall,
+        submission::Step,
+    };
+
+    use crate::common::get_pipeline;
+
+    #[test]
+    fn test_calldata_gas() {
+        assert_eq!(calldata_gas(&[]), 0);
+        assert_eq!(calldata_gas(&[0, 0, 1, 0xff]), 4 + 4 + 16 + 16);
+    }
+
+    #[test]
+    fn test_pipeline_calldata() {
+        let pipeline = get_pipeline(Path::new("unused.json"));
+        let steps = pipeline.steps();
+
+        let merkle = pipeline.calldata(&steps[0]).unwrap();
+        assert_eq!(merkle[..4], VerifyMerkleCall::selector());
+        assert_eq!(
+            pipeline.target(&steps[0]),
+            pipeline.contracts.merkle_statement
+        );
+
+        let main = pipeline.calldata(&Step::MainProof).unwrap();
+        assert_eq!(main[..4], VerifyProofAndRegisterCall::selector());
+        assert_eq!(
+            pipeline.target(&Step::MainProof),
+            pipeline.contracts.gps_statement_verifier
+        );
+    }
+
+    #[test]
+    fn test_estimate_offline() {
+        let pipeline = get_pipeline(Path::new("unused.json"));
+        let model = GasModel::default();
+        let report = gas::estimate_offline(&pipeline, &model).unwrap();
+
+        let steps = pipeline.steps();
+        assert_eq!(report.calls.len(), steps.len());
+        for (call, step) in report.calls.iter().zip(&steps) {
+            let calldata = pipeline.calldata(step).unwrap();
+            assert_eq!(call.step, step.name());
+            assert_eq!(call.calldata_len, calldata.len());
+            assert_eq!(call.source, GasSource::Model);
+            assert!(call.gas > U256::from(TX_BASE_GAS + calldata_gas(&calldata)));
+        }
+
+        let total = report
+            .calls
+            .iter()
+            .fold(U256::zero(), |total, call| total + call.gas);
+        assert_eq!(report.total_gas(), total);
+        assert_eq!(report.cost(U256::from(2)), total * 2);
+
+        let table = report.table(U256::exp10(9));
+        assert!(table.contains("Main proof"));
+        assert!(table.contains("Cost at 1.000000000 gwei"));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_falls_back_to_model() {
+        let pipeline = get_pipeline(Path::new("unused.json"));
+        let model = GasModel::default();
+        let n_steps = pipeline.steps().len();
+
+        // The node estimates the first step, and the others revert.
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        for _ in 1..n_steps {
+            mock.push_response(MockResponse::Error(JsonRpcError {
+                code: 3,
+                message: "execution reverted".to_string(),
+                data: None,
+            }));
+        }
+        mock.push(U256::from(1_000_000)).unwrap();
+
+        let report = gas::estimate(&pipeline, &provider, None, &model)
+            .await
+            .unwrap();
+        let offline = gas::estimate_offline(&pipeline, &model).unwrap();
+
+        assert_eq!(report.calls[0].source, GasSource::Node);
+        assert_eq!(report.calls[0].gas, U256::from(1_000_000));
+        assert!(report.calls[1..]
+            .iter()
+            .all(|call| matches!(call.source, GasSource::ModelFallback(_))));
+        for (call, offline) in report.calls[1..].iter().zip(&offline.calls[1..]) {
+            assert_eq!(call.gas, offline.gas);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_estimate_fails_on_unreachable_node() {
+        let pipeline = get_pipeline(Path::new("unused.json"));
+        let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+
+        match gas::estimate(&pipeline, &provider, None, &GasModel::default()).await {
+            Err(AdapterError::Submission(SubmissionError::Provider { step, .. })) => {
+                assert_eq!(step, pipeline.steps()[0].name())
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_estimate_checks_chain_id() {
+        let mut pipeline = get_pipeline(Path::new("unused.json"));
+        pipeline.chain_id = Some(1);
+        let (provider, mock) = Provider::<MockProvider>::mocked();
+        mock.push(U256::from(11155111)).unwrap();
+
+        match gas::estimate(&pipeline, &provider, None, &GasModel::default()).await {
+            Err(AdapterError::Submission(SubmissionError::ChainIdMismatch { expected, found })) => {
+                assert_eq!(expected, 1);
+                assert_eq!(found, U256::from(11155111));
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+}
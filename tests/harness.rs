@@ -0,0 +1,91 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethers::types::Address;
+    use stark_evm_adapter::{
+        harness::{DeploymentTransaction, LocalVerifier, VerifierDeployment},
+        submission::{ContractAddresses, Pipeline, StepStatus},
+    };
+
+    use crate::common::{get_bootloader_split_proofs, TempDir};
+
+    /// The recorded deployment of the verifier stack, see the README on how to generate it.
+    const DEPLOYMENT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/evm/verifier_stack.json"
+    );
+
+    #[test]
+    fn test_deployment_json() {
+        let deployment = VerifierDeployment {
+            contracts: ContractAddresses {
+                merkle_statement: Address::random(),
+                fri_statement: Address::random(),
+                memory_page_fact_registry: Address::random(),
+                gps_statement_verifier: Address::random(),
+            },
+            transactions: vec![
+                DeploymentTransaction {
+                    name: "MerkleStatementContract".to_string(),
+                    to: None,
+                    data: vec![0x60, 0x80].into(),
+                },
+                DeploymentTransaction {
+                    name: "configure".to_string(),
+                    to: Some(Address::random()),
+                    data: vec![0x12, 0x34, 0x56, 0x78].into(),
+                },
+            ],
+        };
+
+        let dir = TempDir::new("deployment_json");
+        let path = dir.join("verifier_stack.json");
+        std::fs::write(&path, serde_json::to_string(&deployment).unwrap()).unwrap();
+
+        let loaded = VerifierDeployment::load(&path).unwrap();
+        assert_eq!(
+            loaded.contracts.gps_statement_verifier,
+            deployment.contracts.gps_statement_verifier
+        );
+        assert_eq!(loaded.transactions[0].to, None);
+        assert_eq!(loaded.transactions[1].data, deployment.transactions[1].data);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs anvil and the verifier stack bytecode in tests/fixtures/evm/verifier_stack.json, see the README"]
+    async fn test_split_register_verify_on_local_chain() {
+        let deployment = VerifierDeployment::load(DEPLOYMENT).unwrap();
+        let verifier = LocalVerifier::deploy(&deployment).await.unwrap();
+
+        let split_proofs = get_bootloader_split_proofs();
+        split_proofs.verify_locally().unwrap();
+        let fact_topologies = split_proofs
+            .main_proof
+            .public_input
+            .fact_topologies(true, &HashMap::new())
+            .unwrap();
+
+        let dir = TempDir::new("local_chain");
+        let state_file = dir.join("state.json");
+
+        let pipeline = Pipeline::new(
+            split_proofs,
+            fact_topologies,
+            verifier.contracts.clone(),
+            &state_file,
+        )
+        .unwrap();
+        let state = pipeline.run(verifier.client.clone()).await.unwrap();
+
+        assert_eq!(state.steps.len(), pipeline.steps().len());
+        assert!(state
+            .steps
+            .iter()
+            .all(|record| record.status == StepStatus::Confirmed));
+    }
+}
@@ -0,0 +1,116 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+use stark_evm_adapter::layout::Layout;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+    use stark_evm_adapter::annotated_proof::AnnotatedProof;
+    use stark_evm_adapter::annotation_parser::split_fri_merkle_statements;
+    use stark_evm_adapter::errors::PublicInputError;
+    use stark_evm_adapter::oods_statement::MainProof;
+
+    use crate::common::get_split_proofs;
+
+    fn get_main_proof() -> MainProof {
+        get_split_proofs().main_proof
+    }
+
+    #[test]
+    fn test_layout_names() {
+        for layout in Layout::ALL {
+            assert_eq!(layout.name().parse::<Layout>().unwrap(), layout);
+            assert_eq!(
+                layout.segment_names()[..3],
+                ["program", "execution", "output"]
+            );
+        }
+        let error = "all_cairo".parse::<Layout>().unwrap_err();
+        assert_eq!(
+            error,
+            PublicInputError::UnsupportedLayout {
+                layout: "all_cairo".to_string()
+            }
+        );
+        for layout in Layout::ALL {
+            assert!(error.to_string().contains(layout.name()));
+        }
+    }
+
+    #[test]
+    fn test_small_layout_verifier_id() {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+        let annotated_proof = AnnotatedProof::from_stone_files(
+            format!("{}/stone_proof.json", fixtures),
+            format!("{}/stone_proof_annotation.txt", fixtures),
+            format!("{}/stone_proof_annotation_extra.txt", fixtures),
+        )
+        .unwrap();
+        let main_proof = split_fri_merkle_statements(annotated_proof)
+            .unwrap()
+            .main_proof;
+
+        let call = main_proof.contract_function_call(vec![]).unwrap();
+        assert_eq!(call.cairo_verifier_id, U256::one());
+
+        // program, execution, output, pedersen, range_check and ecdsa bounds follow the layout
+        let segments = &main_proof.public_input.memory_segments;
+        let expected: Vec<U256> = [
+            "program",
+            "execution",
+            "output",
+            "pedersen",
+            "range_check",
+            "ecdsa",
+        ]
+        .iter()
+        .flat_map(|name| [segments[*name].begin_addr, segments[*name].stop_ptr])
+        .map(U256::from)
+        .collect();
+        assert_eq!(call.cairo_aux_input[4..4 + expected.len()], expected);
+    }
+
+    #[test]
+    fn test_layout_errors() {
+        let mut main_proof = get_main_proof();
+        assert_eq!(
+            main_proof
+                .contract_function_call(vec![])
+                .unwrap()
+                .cairo_verifier_id,
+            U256::from(6)
+        );
+
+        main_proof.public_input.layout = "all_solidity".to_string();
+        assert_eq!(
+            main_proof.contract_function_call(vec![]).unwrap_err(),
+            PublicInputError::NoCairoVerifier {
+                layout: Layout::AllSolidity
+            }
+        );
+
+        main_proof.public_input.layout = "recursive_with_poseidon".to_string();
+        assert!(matches!(
+            main_proof.contract_function_call(vec![]),
+            Err(PublicInputError::UnknownSegment { .. })
+        ));
+
+        main_proof.public_input.layout = "starknet_with_keccak".to_string();
+        assert_eq!(
+            main_proof.contract_function_call(vec![]).unwrap_err(),
+            PublicInputError::MissingSegment {
+                layout: Layout::StarknetWithKeccak,
+                name: "keccak".to_string()
+            }
+        );
+
+        main_proof.public_input.layout = "starknet_v2".to_string();
+        assert!(matches!(
+            main_proof.contract_function_call(vec![]),
+            Err(PublicInputError::UnsupportedLayout { .. })
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ethers::abi::{AbiEncode, Token};
+    use ethers::providers::Provider;
+    use ethers::types::{Address, H256, U256};
+    use ethers::utils::keccak256;
+    use stark_evm_adapter::errors::{AdapterError, MerkleVerificationError, SubmissionError};
+    use stark_evm_adapter::merkle_statement::{MerkleStatement, MAX_N_MERKLE_VERIFIER_QUERIES};
+    use stark_evm_adapter::submission::{ContractAddresses, Pipeline};
+
+    use crate::common::get_split_proofs;
+
+    #[test]
+    fn test_merkle_statements_verify_locally() {
+        let split_proofs = get_split_proofs();
+        for (name, statement) in split_proofs.merkle_statements.iter() {
+            assert_eq!(statement.verify_locally(), Ok(()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_tampered_merkle_statement_is_rejected() {
+        let split_proofs = get_split_proofs();
+        let statement = split_proofs.merkle_statements.get("Trace 0").unwrap();
+
+        let mut json = serde_json::to_value(statement).unwrap();
+        json["proof"][0] = serde_json::to_value(ethers::types::U256::from(1)).unwrap();
+        let tampered: MerkleStatement = serde_json::from_value(json).unwrap();
+
+        match tampered.verify_locally() {
+            Err(MerkleVerificationError::RootMismatch { expected, .. }) => {
+                assert_eq!(expected, statement.contract_function_call().expected_root)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut json = serde_json::to_value(statement).unwrap();
+        json["proof"] = serde_json::json!([]);
+        let truncated: MerkleStatement = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            truncated.verify_locally(),
+            Err(MerkleVerificationError::MissingAuthenticationNode { .. })
+        ));
+    }
+
+    #[test]
+    fn test_merkle_statement_fact_hash() {
+        let split_proofs = get_split_proofs();
+        let statement = split_proofs.merkle_statements.get("Trace 0").unwrap();
+        let call = statement.contract_function_call();
+
+        // The registry hashes the (index, value) queue followed by the root.
+        let mut words: Vec<Token> = call.merkle_queue.into_iter().map(Token::Uint).collect();
+        words.push(Token::Uint(call.expected_root));
+        let expected = H256::from(keccak256(ethers::abi::encode(&words)));
+
+        assert_eq!(statement.fact_hash(), expected);
+    }
+
+    #[test]
+    fn test_merkle_statement_verify_with_any_middleware() {
+        let split_proofs = get_split_proofs();
+        let statement = split_proofs.merkle_statements.get("Trace 0").unwrap();
+
+        let (provider, _mock) = Provider::mocked();
+        let call = statement.verify(Address::zero(), Arc::new(provider));
+
+        assert_eq!(
+            call.calldata().unwrap(),
+            statement.contract_function_call().encode()
+        );
+    }
+
+    /// A statement opening every leaf of a tree of the given height, with no authentication nodes.
+    fn full_tree_statement(height: usize) -> MerkleStatement {
+        let mask = !((U256::one() << 96) - 1);
+        let n_leaves = 1usize << height;
+        let mut layer: Vec<U256> = (0..n_leaves).map(|i| U256::from(i + 1) << 96).collect();
+        let leaves = layer.clone();
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(2)
+                .map(|pair| {
+                    let words = [Token::Uint(pair[0]), Token::Uint(pair[1])];
+                    U256::from_big_endian(&keccak256(ethers::abi::encode(&words))) & mask
+                })
+                .collect();
+        }
+
+        MerkleStatement::new(
+            layer[0],
+            n_leaves,
+            height,
+            (n_leaves..2 * n_leaves).map(U256::from).collect(),
+            leaves,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_split_merkle_statement() {
+        let split_proofs = get_split_proofs();
+        let statement = split_proofs.merkle_statements.get("Trace 0").unwrap();
+        let call = statement.contract_function_call();
+
+        for max_queries in [1, 3, statement.n_queries()] {
+            let chunks = statement.split(max_queries).unwrap();
+            assert_eq!(chunks.len(), statement.n_queries().div_ceil(max_queries));
+
+            let mut merkle_queue = vec![];
+            for chunk in &chunks {
+                assert!(chunk.n_queries() <= max_queries);
+                assert_eq!(chunk.verify_locally(), Ok(()));
+                merkle_queue.extend(chunk.contract_function_call().merkle_queue);
+            }
+            assert_eq!(merkle_queue, call.merkle_queue);
+        }
+
+        // A single chunk is the statement itself, any other split registers different facts.
+        let whole = statement.split(statement.n_queries()).unwrap();
+        assert_eq!(whole[0].contract_function_call(), call);
+        assert_eq!(whole[0].fact_hash(), statement.fact_hash());
+        let chunks = statement.split(1).unwrap();
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.fact_hash() != statement.fact_hash()));
+    }
+
+    #[test]
+    fn test_split_oversized_merkle_statement() {
+        let statement = full_tree_statement(8);
+        assert_eq!(
+            statement.verify_locally(),
+            Err(MerkleVerificationError::TooManyQueries {
+                n_queries: 256,
+                max: MAX_N_MERKLE_VERIFIER_QUERIES,
+            })
+        );
+
+        assert!(matches!(
+            statement.split(0),
+            Err(MerkleVerificationError::EmptySplit)
+        ));
+        let chunks = statement.split(usize::MAX).unwrap();
+        assert_eq!(chunks.len(), 2);
+        let chunks = statement.split(100).unwrap();
+        assert_eq!(
+            chunks.iter().map(|c| c.n_queries()).collect::<Vec<_>>(),
+            vec![100, 100, 56]
+        );
+        for chunk in &chunks {
+            assert_eq!(chunk.verify_locally(), Ok(()));
+        }
+
+        let mut json = serde_json::to_value(&statement).unwrap();
+        json["merkle_queue_values"][0] = serde_json::to_value(U256::from(1)).unwrap();
+        let tampered: MerkleStatement = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            tampered.split(100),
+            Err(MerkleVerificationError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_authentication_node_is_located() {
+        // Opening leaf 8 of a tree of height 3 reads nodes 9, 5 and 3 from the proof.
+        let statement = full_tree_statement(3).split(1).unwrap().remove(0);
+        let corrupt = |position: usize| {
+            let mut json = serde_json::to_value(&statement).unwrap();
+            let node: U256 = serde_json::from_value(json["proof"][position].clone()).unwrap();
+            json["proof"][position] = serde_json::to_value(node + 1).unwrap();
+            let tampered: MerkleStatement = serde_json::from_value(json).unwrap();
+            (node + 1, tampered.verify_locally())
+        };
+
+        let (hash, result) = corrupt(1);
+        assert_eq!(
+            result,
+            Err(MerkleVerificationError::NodeMismatch {
+                node: U256::from(5),
+                leaf: U256::from(8),
+                hash,
+            })
+        );
+
+        // A leaf can hold any value, so a wrong sibling leaf only shows at the root.
+        assert!(matches!(
+            corrupt(0).1,
+            Err(MerkleVerificationError::RootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_pipeline_rejects_oversized_merkle_statement() {
+        let mut split_proofs = get_split_proofs();
+        split_proofs
+            .merkle_statements
+            .insert("Trace 0".to_string(), full_tree_statement(8));
+
+        let contracts = ContractAddresses {
+            merkle_statement: Address::random(),
+            fri_statement: Address::random(),
+            memory_page_fact_registry: Address::random(),
+            gps_statement_verifier: Address::random(),
+        };
+        match Pipeline::new(split_proofs, vec![], contracts, "unused.json") {
+            Err(AdapterError::Submission(SubmissionError::MerkleStatementTooLarge {
+                name,
+                n_queries,
+                max,
+            })) => {
+                assert_eq!(name, "Trace 0");
+                assert_eq!(n_queries, 256);
+                assert_eq!(max, MAX_N_MERKLE_VERIFIER_QUERIES);
+            }
+            other => panic!("unexpected result: {:?}", other.err()),
+        }
+    }
+}
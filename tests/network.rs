@@ -0,0 +1,75 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ethers::types::Address;
+    use stark_evm_adapter::{
+        errors::{AdapterError, ParseError},
+        network::{NetworkConfig, BUILTIN_NETWORKS},
+    };
+
+    use crate::common::TempDir;
+
+    #[test]
+    fn test_builtin_networks() {
+        for name in BUILTIN_NETWORKS {
+            let network: NetworkConfig = name.parse().unwrap();
+            assert_eq!(network.name, *name);
+        }
+
+        let mainnet = NetworkConfig::mainnet();
+        assert_eq!(mainnet.chain_id, 1);
+        assert_eq!(
+            mainnet.contracts.gps_statement_verifier,
+            Address::from_str("0xd51a3d50d4d2f99a345a66971e650eea064dd8df").unwrap()
+        );
+
+        assert!(NetworkConfig::builtin("goerli").is_none());
+        assert!(matches!(
+            "goerli".parse::<NetworkConfig>(),
+            Err(AdapterError::UnknownNetwork { name }) if name == "goerli"
+        ));
+    }
+
+    #[test]
+    fn test_load_network_config() {
+        let dir = TempDir::new("network_config");
+
+        let toml_path = dir.join("stack.toml");
+        std::fs::write(
+            &toml_path,
+            r#"
+name = "my-stack"
+chain_id = 31337
+
+[contracts]
+merkle_statement = "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+fri_statement = "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512"
+memory_page_fact_registry = "0x9fe46736679d2d9a65f0992f2272de9f3c7fa6e0"
+gps_statement_verifier = "0xcf7ed3acca5a467e9e704c703e8d87f634fb0fc9"
+"#,
+        )
+        .unwrap();
+        let network = NetworkConfig::load(&toml_path).unwrap();
+        assert_eq!(network.name, "my-stack");
+        assert_eq!(network.chain_id, 31337);
+        assert_eq!(
+            network.contracts.fri_statement,
+            Address::from_str("0xe7f1725e7734ce288f8367e1bb143e90bb3f0512").unwrap()
+        );
+
+        let json_path = dir.join("stack.json");
+        std::fs::write(&json_path, serde_json::to_string(&network).unwrap()).unwrap();
+        assert_eq!(NetworkConfig::load(&json_path).unwrap(), network);
+
+        std::fs::write(&toml_path, "name = \"incomplete\"").unwrap();
+        assert!(matches!(
+            NetworkConfig::load(&toml_path),
+            Err(AdapterError::Parse(ParseError::InvalidToml(_)))
+        ));
+    }
+}
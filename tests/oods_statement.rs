@@ -0,0 +1,293 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethers::abi::Token;
+    use ethers::types::{H256, U256};
+    use ethers::utils::keccak256;
+    use stark_evm_adapter::annotated_proof::PublicInput;
+    use stark_evm_adapter::default_prime;
+    use stark_evm_adapter::errors::{AdapterError, TopologyError};
+    use stark_evm_adapter::oods_statement::{program_fact, FactTopology};
+
+    use crate::common::{get_bootloader_proof, get_bootloader_split_proofs};
+
+    /// Moves the public memory cell at `address` to `page`.
+    fn set_page(public_input: &mut PublicInput, address: u32, page: u32) {
+        public_input
+            .public_memory
+            .iter_mut()
+            .find(|cell| cell.address == address)
+            .unwrap()
+            .page = page;
+    }
+
+    /// The public input of a simple bootloader run of the same task: the output segment starts
+    /// at the number of tasks, without the bootloader config.
+    fn simple_bootloader_input(mut public_input: PublicInput) -> PublicInput {
+        public_input
+            .memory_segments
+            .get_mut("output")
+            .unwrap()
+            .begin_addr += 2;
+        public_input
+    }
+
+    /// Sets the number of tasks in the bootloader output of `public_input`.
+    fn set_n_tasks(public_input: &mut PublicInput, include_bootloader_config: bool, n_tasks: u64) {
+        let mut address = public_input.memory_segments["output"].begin_addr;
+        if include_bootloader_config {
+            address += 2;
+        }
+        public_input
+            .public_memory
+            .iter_mut()
+            .find(|cell| cell.address == address)
+            .unwrap()
+            .value = n_tasks.into();
+    }
+
+    #[test]
+    fn test_simple_bootloader_output() {
+        let mut split_proofs = get_bootloader_split_proofs();
+        let main_proof = &mut split_proofs.main_proof;
+        let topologies = main_proof
+            .public_input
+            .fact_topologies(true, &HashMap::new())
+            .unwrap();
+        let metadata = main_proof
+            .generate_tasks_metadata(true, topologies.clone())
+            .unwrap();
+
+        main_proof.public_input = simple_bootloader_input(main_proof.public_input.clone());
+        let simple_topologies = main_proof
+            .public_input
+            .fact_topologies(false, &HashMap::new())
+            .unwrap();
+        assert_eq!(simple_topologies, topologies);
+        assert_eq!(
+            main_proof
+                .generate_tasks_metadata(false, simple_topologies)
+                .unwrap(),
+            metadata
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_output_offsets() {
+        // [n_tasks = 0, 4, program hash, 10, 144] leaves everything after n_tasks unprocessed, and
+        // both output formats report it relative to n_tasks.
+        let expected = TopologyError::UnprocessedOutput {
+            processed: 1,
+            len: 5,
+        };
+        let bootloader_input = get_bootloader_proof().public_input;
+        let simple_input = simple_bootloader_input(bootloader_input.clone());
+        for (mut public_input, include_bootloader_config) in
+            [(bootloader_input, true), (simple_input, false)]
+        {
+            set_n_tasks(&mut public_input, include_bootloader_config, 0);
+            match public_input.fact_topologies(include_bootloader_config, &HashMap::new()) {
+                Err(AdapterError::Topology(err)) => assert_eq!(err, expected),
+                other => panic!("unexpected result: {:?}", other),
+            }
+
+            let mut split_proofs = get_bootloader_split_proofs();
+            split_proofs.main_proof.public_input = public_input;
+            match split_proofs
+                .main_proof
+                .generate_tasks_metadata(include_bootloader_config, vec![])
+            {
+                Err(AdapterError::Topology(err)) => assert_eq!(err, expected),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fact_topologies_match_bootloader_runner() {
+        let topologies_file = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/bootloader/fact_topologies.json"
+        ));
+        let topology_json: serde_json::Value = serde_json::from_str(topologies_file).unwrap();
+        let expected: Vec<FactTopology> =
+            serde_json::from_value(topology_json["fact_topologies"].clone()).unwrap();
+
+        let split_proofs = get_bootloader_split_proofs();
+        let main_proof = &split_proofs.main_proof;
+        let derived = main_proof
+            .public_input
+            .fact_topologies(true, &HashMap::new())
+            .unwrap();
+        assert_eq!(derived, expected);
+
+        assert_eq!(
+            main_proof.generate_tasks_metadata(true, derived).unwrap(),
+            main_proof.generate_tasks_metadata(true, expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tasks_metadata_facts() {
+        let split_proofs = get_bootloader_split_proofs();
+        let main_proof = &split_proofs.main_proof;
+        let fact_topologies = main_proof
+            .public_input
+            .fact_topologies(true, &HashMap::new())
+            .unwrap();
+        let metadata = main_proof
+            .generate_tasks_metadata(true, fact_topologies)
+            .unwrap();
+
+        // [n_tasks, output size, program hash, n tree pairs, tree structure..]
+        let program_hash = metadata.task_metadata[2];
+        assert_eq!(
+            metadata.task_metadata,
+            vec![
+                U256::one(),
+                U256::from(4),
+                program_hash,
+                U256::one(),
+                U256::one(),
+                U256::zero()
+            ]
+        );
+        assert_eq!(metadata.page_sizes, vec![2]);
+
+        // With the trivial topology, the output root is the hash of the single page: fib(10) = 144.
+        let page: Vec<Token> = [U256::from(10), U256::from(144)]
+            .into_iter()
+            .map(Token::Uint)
+            .collect();
+        let output_root = U256::from(keccak256(ethers::abi::encode(&page)));
+        assert_eq!(metadata.output_roots, vec![output_root]);
+
+        let words = [Token::Uint(program_hash), Token::Uint(output_root)];
+        let fact = H256::from(keccak256(ethers::abi::encode(&words)));
+        assert_eq!(metadata.facts, vec![fact]);
+        assert_eq!(program_fact(program_hash, output_root), fact);
+    }
+
+    #[test]
+    fn test_fact_topologies_without_pages() {
+        // The task output [0xa, 0x90] is at addresses 2120 and 2121, in page 1.
+        let mut public_input = get_bootloader_proof().public_input;
+        set_page(&mut public_input, 2120, 0);
+        set_page(&mut public_input, 2121, 0);
+
+        let topologies = public_input.fact_topologies(true, &HashMap::new()).unwrap();
+        assert_eq!(topologies, vec![FactTopology::new(vec![1, 0], vec![2])]);
+    }
+
+    #[test]
+    fn test_fact_topologies_with_several_pages() {
+        let mut public_input = get_bootloader_proof().public_input;
+        set_page(&mut public_input, 2121, 2);
+
+        let err = public_input
+            .fact_topologies(true, &HashMap::new())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AdapterError::Topology(TopologyError::MissingTreeStructure {
+                task: 0,
+                n_pages: 2
+            })
+        ));
+
+        let tree_structures = HashMap::from([(0, vec![2, 1])]);
+        let topologies = public_input
+            .fact_topologies(true, &tree_structures)
+            .unwrap();
+        assert_eq!(topologies, vec![FactTopology::new(vec![2, 1], vec![1, 1])]);
+    }
+
+    #[test]
+    fn test_fact_topologies_with_leading_main_page() {
+        // A task whose output starts in the main page, with its next pages from page 1 on.
+        let mut public_input = get_bootloader_proof().public_input;
+        set_page(&mut public_input, 2120, 0);
+
+        let tree_structures = HashMap::from([(0, vec![2, 1])]);
+        let topologies = public_input
+            .fact_topologies(true, &tree_structures)
+            .unwrap();
+        assert_eq!(topologies, vec![FactTopology::new(vec![2, 1], vec![1, 1])]);
+
+        // The main page can only come first.
+        set_page(&mut public_input, 2120, 1);
+        set_page(&mut public_input, 2121, 0);
+        let err = public_input
+            .fact_topologies(true, &tree_structures)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AdapterError::Topology(TopologyError::UnexpectedPage {
+                task: 0,
+                page: 0,
+                expected: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fact_topologies_reject_unexpected_page() {
+        let mut public_input = get_bootloader_proof().public_input;
+        set_page(&mut public_input, 2120, 3);
+        set_page(&mut public_input, 2121, 3);
+
+        let err = public_input
+            .fact_topologies(true, &HashMap::new())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AdapterError::Topology(TopologyError::UnexpectedPage {
+                task: 0,
+                page: 3,
+                expected: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_continuous_memory_page_fact_hash() {
+        let split_proofs = get_bootloader_split_proofs();
+        let main_proof = &split_proofs.main_proof;
+        let (_, continuous_pages) = main_proof.memory_page_registration_args().unwrap();
+        assert_eq!(continuous_pages.len(), 1);
+
+        // The cairo aux input ends with
+        // [.., page 1 start, page 1 size, page 1 hash, page 0 prod, page 1 prod, z, alpha].
+        let aux = main_proof
+            .contract_function_call(vec![U256::zero()])
+            .unwrap()
+            .cairo_aux_input;
+        let n = aux.len();
+        let (start, size, hash, prod) = (aux[n - 7], aux[n - 6], aux[n - 5], aux[n - 3]);
+
+        let words: Vec<Token> = [
+            U256::one(),
+            default_prime(),
+            size,
+            main_proof.interaction_z,
+            main_proof.interaction_alpha,
+            prod,
+            hash,
+            start,
+        ]
+        .into_iter()
+        .map(Token::Uint)
+        .collect();
+        let expected = H256::from(keccak256(ethers::abi::encode(&words)));
+
+        assert_eq!(
+            main_proof.continuous_memory_page_fact_hash(&continuous_pages[0]),
+            expected
+        );
+    }
+}
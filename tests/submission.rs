@@ -0,0 +1,244 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::TryFrom, sync::Arc};
+
+    use ethers::{
+        middleware::SignerMiddleware,
+        providers::{Http, MockProvider, Provider},
+        signers::LocalWallet,
+        types::{Bytes, Transaction, TransactionReceipt, H256, U256},
+    };
+    use stark_evm_adapter::{
+        errors::{AdapterError, SubmissionError},
+        submission::{Pipeline, Step, StepRecord, StepStatus, SubmissionState},
+    };
+
+    use crate::common::{get_pipeline, TempDir};
+
+    /// A signer whose provider cannot be reached, so any attempt to send a transaction fails.
+    fn offline_signer() -> Arc<SignerMiddleware<Provider<Http>, LocalWallet>> {
+        let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+        let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+        Arc::new(SignerMiddleware::new(provider, wallet))
+    }
+
+    fn confirmed_state(pipeline: &Pipeline) -> SubmissionState {
+        SubmissionState {
+            steps: pipeline
+                .steps()
+                .iter()
+                .map(|step| StepRecord {
+                    step: step.name(),
+                    tx_hash: Some(H256::random()),
+                    facts: pipeline.facts(step).unwrap(),
+                    status: StepStatus::Confirmed,
+                    replaced: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_submission_steps_order() {
+        let dir = TempDir::new("steps_order");
+        let pipeline = get_pipeline(&dir.join("state.json"));
+        let steps = pipeline.steps();
+
+        let n_traces = pipeline.split_proofs.merkle_statements.len();
+        let n_fris = pipeline.split_proofs.fri_merkle_statements.len();
+        assert_eq!(steps.len(), n_traces + n_fris + 1 + 1);
+        assert_eq!(steps[0], Step::MerkleStatement("Trace 0".to_string()));
+        assert_eq!(steps[n_traces], Step::FriStatement(0));
+        assert_eq!(steps[n_traces + n_fris], Step::ContinuousMemoryPage(0));
+        assert_eq!(steps.last(), Some(&Step::MainProof));
+    }
+
+    #[tokio::test]
+    async fn test_submission_resumes_from_state_file() {
+        let dir = TempDir::new("resume");
+        let state_file = dir.join("state.json");
+        let mut pipeline = get_pipeline(&state_file);
+
+        // Every step is already confirmed, nothing is sent.
+        let state = confirmed_state(&pipeline);
+        state.save(&state_file).unwrap();
+        let resumed = pipeline.run(offline_signer()).await.unwrap();
+        assert_eq!(resumed.steps.len(), state.steps.len());
+
+        // A state recorded for another proof is rejected.
+        let mut state = confirmed_state(&pipeline);
+        state.steps[0].facts = vec![H256::random()];
+        state.save(&state_file).unwrap();
+        assert!(matches!(
+            pipeline.run(offline_signer()).await,
+            Err(AdapterError::Submission(
+                SubmissionError::StateMismatch { .. }
+            ))
+        ));
+
+        // The main proof records the fact of every task.
+        let mut state = confirmed_state(&pipeline);
+        let main_proof = state.steps.last_mut().unwrap();
+        assert!(!main_proof.facts.is_empty());
+        main_proof.facts.push(H256::random());
+        state.save(&state_file).unwrap();
+        match pipeline.run(offline_signer()).await {
+            Err(AdapterError::Submission(SubmissionError::StateMismatch { step, .. })) => {
+                assert_eq!(step, "Main proof")
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The main proof is not confirmed yet, so the run tries to send it.
+        pipeline.skip_registered = false;
+        let mut state = confirmed_state(&pipeline);
+        state.steps.pop();
+        state.save(&state_file).unwrap();
+        match pipeline.run(offline_signer()).await {
+            Err(AdapterError::Submission(SubmissionError::Contract { step, .. })) => {
+                assert_eq!(step, "Main proof")
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    /// A client whose `n_polls` next lookups of a transaction find nothing, after which the
+    /// transaction is reported as dropped, or a client seeing it mined if `n_polls` is 0.
+    fn pending_tx_client(n_polls: usize) -> Arc<Provider<MockProvider>> {
+        let (provider, mock) = Provider::mocked();
+        if n_polls == 0 {
+            // responses are popped from the back
+            let receipt = TransactionReceipt {
+                block_number: Some(1.into()),
+                status: Some(1.into()),
+                ..Default::default()
+            };
+            mock.push(receipt).unwrap();
+            let tx = Transaction {
+                block_number: Some(1.into()),
+                ..Default::default()
+            };
+            mock.push(tx).unwrap();
+        }
+        for _ in 0..n_polls {
+            mock.push::<Option<Transaction>, _>(None).unwrap();
+        }
+        Arc::new(provider.interval(std::time::Duration::from_millis(1)))
+    }
+
+    #[tokio::test]
+    async fn test_submission_waits_for_pending_transaction() {
+        let dir = TempDir::new("pending");
+        let state_file = dir.join("state.json");
+        let mut pipeline = get_pipeline(&state_file);
+        pipeline.skip_registered = false;
+
+        // The main proof was sent before a crash, and is mined by the time the run resumes.
+        let original = H256::random();
+        let mut state = confirmed_state(&pipeline);
+        let main_proof = state.steps.last_mut().unwrap();
+        main_proof.tx_hash = Some(original);
+        main_proof.status = StepStatus::Pending;
+        state.save(&state_file).unwrap();
+        let resumed = pipeline.run(pending_tx_client(0)).await.unwrap();
+        let main_proof = resumed.steps.last().unwrap();
+        assert_eq!(main_proof.status, StepStatus::Confirmed);
+        assert_eq!(main_proof.tx_hash, Some(original));
+
+        // It was dropped instead, so the step is recorded as such and sent again.
+        state.save(&state_file).unwrap();
+        match pipeline.run(pending_tx_client(4)).await {
+            Err(AdapterError::Submission(SubmissionError::Contract { step, .. })) => {
+                assert_eq!(step, "Main proof")
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let saved = SubmissionState::load(&state_file).unwrap();
+        let main_proof = saved.get("Main proof").unwrap();
+        assert_eq!(main_proof.status, StepStatus::Dropped);
+        assert_eq!(main_proof.tx_hash, Some(original));
+
+        // The next record of the step reports the hash of the dropped transaction.
+        pipeline.skip_registered = true;
+        let resumed = pipeline.run(fact_registry_client(true, 8)).await.unwrap();
+        let main_proof = resumed.get("Main proof").unwrap();
+        assert_eq!(main_proof.status, StepStatus::Skipped);
+        assert_eq!(main_proof.replaced, vec![original]);
+    }
+
+    /// A client answering `is_valid` with `valid` for each of the `n_calls` next calls.
+    fn fact_registry_client(valid: bool, n_calls: usize) -> Arc<Provider<MockProvider>> {
+        let (provider, mock) = Provider::mocked();
+        let mut word = [0u8; 32];
+        word[31] = valid as u8;
+        for _ in 0..n_calls {
+            mock.push::<Bytes, _>(Bytes::from(word.to_vec())).unwrap();
+        }
+        Arc::new(provider)
+    }
+
+    #[tokio::test]
+    async fn test_submission_skips_registered_facts() {
+        let dir = TempDir::new("skip_registered");
+        let state_file = dir.join("state.json");
+        let pipeline = get_pipeline(&state_file);
+        let n_steps = pipeline.steps().len();
+
+        let plan = pipeline
+            .preflight(fact_registry_client(false, n_steps))
+            .await
+            .unwrap();
+        assert_eq!(plan.len(), n_steps);
+        assert!(plan.iter().all(|(_, registered)| !registered));
+
+        // Every fact is registered, so nothing is sent and every step is skipped.
+        let state = pipeline
+            .run(fact_registry_client(true, n_steps))
+            .await
+            .unwrap();
+        assert_eq!(state.steps.len(), n_steps);
+        for (record, step) in state.steps.iter().zip(pipeline.steps()) {
+            assert_eq!(record.step, step.name());
+            assert_eq!(record.status, StepStatus::Skipped);
+            assert_eq!(record.tx_hash, None);
+            assert_eq!(record.facts, pipeline.facts(&step).unwrap());
+        }
+
+        // A resumed run does not query the skipped steps again.
+        let resumed = pipeline.run(fact_registry_client(true, 0)).await.unwrap();
+        assert_eq!(resumed.steps.len(), n_steps);
+    }
+
+    #[tokio::test]
+    async fn test_submission_checks_chain_id() {
+        let dir = TempDir::new("chain_id");
+        let mut pipeline = get_pipeline(&dir.join("state.json"));
+        pipeline.chain_id = Some(1);
+
+        let client = |chain_id: u64| {
+            let (provider, mock) = Provider::mocked();
+            mock.push(U256::from(chain_id)).unwrap();
+            Arc::new(provider)
+        };
+        assert!(matches!(
+            pipeline.preflight(client(11155111)).await,
+            Err(AdapterError::Submission(SubmissionError::ChainIdMismatch {
+                expected: 1,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            pipeline.run(client(11155111)).await,
+            Err(AdapterError::Submission(SubmissionError::ChainIdMismatch {
+                expected: 1,
+                ..
+            }))
+        ));
+        // Nothing was sent, so no state was saved.
+        assert!(!dir.join("state.json").exists());
+    }
+}
@@ -0,0 +1,96 @@
+extern crate stark_evm_adapter;
+
+mod common;
+
+use stark_evm_adapter::{
+    annotated_proof::AnnotatedProof,
+    errors::{AdapterError, ProofParametersError, TranscriptMismatch},
+    transcript::replay_transcript,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::common::get_annotated_proof;
+
+    #[test]
+    fn test_replay_transcript() {
+        let annotated_proof = get_annotated_proof();
+        assert_eq!(replay_transcript(&annotated_proof).unwrap(), vec![]);
+
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+        let stone_proof = AnnotatedProof::from_stone_files(
+            format!("{}/stone_proof.json", fixtures),
+            format!("{}/stone_proof_annotation.txt", fixtures),
+            format!("{}/stone_proof_annotation_extra.txt", fixtures),
+        )
+        .unwrap();
+        assert_eq!(replay_transcript(&stone_proof).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_tampered_annotation() {
+        let mut annotated_proof = get_annotated_proof();
+        let (index, line) = annotated_proof
+            .annotations
+            .iter()
+            .enumerate()
+            .find(|(_, line)| line.contains("Evaluation point"))
+            .unwrap();
+        let (prefix, _) = line.split_once("Field Element(").unwrap();
+        annotated_proof.annotations[index] = format!("{}Field Element(0x1)", prefix);
+
+        match replay_transcript(&annotated_proof).unwrap().as_slice() {
+            [TranscriptMismatch::FieldElement {
+                line, annotated, ..
+            }] => {
+                assert_eq!(*line, index + 1);
+                assert_eq!(annotated.as_u64(), 1);
+            }
+            other => panic!("unexpected mismatches: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof_bytes() {
+        // flipping a byte of the first trace commitment changes every value derived after it
+        let mut annotated_proof = get_annotated_proof();
+        let start = if annotated_proof.proof_hex.starts_with("0x") {
+            2
+        } else {
+            0
+        };
+        let replaced = match &annotated_proof.proof_hex[start..start + 1] {
+            "0" => "1",
+            _ => "0",
+        };
+        annotated_proof
+            .proof_hex
+            .replace_range(start..start + 1, replaced);
+
+        let mismatches = replay_transcript(&annotated_proof).unwrap();
+        assert!(matches!(
+            mismatches.first(),
+            Some(TranscriptMismatch::FieldElement { line: 4, .. })
+        ));
+        assert!(mismatches
+            .iter()
+            .any(|mismatch| matches!(mismatch, TranscriptMismatch::ProofOfWork { .. })));
+        assert!(mismatches
+            .iter()
+            .any(|mismatch| matches!(mismatch, TranscriptMismatch::QueryIndex { .. })));
+    }
+
+    #[test]
+    fn test_oversized_evaluation_domain() {
+        let mut annotated_proof = get_annotated_proof();
+        annotated_proof.proof_parameters.stark.log_n_cosets = 60;
+        assert!(matches!(
+            replay_transcript(&annotated_proof),
+            Err(AdapterError::ProofParameters(
+                ProofParametersError::EvaluationDomainTooLarge { log_size: 81 }
+            ))
+        ));
+    }
+}